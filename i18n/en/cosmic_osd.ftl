@@ -2,19 +2,45 @@ invalid-password = Invalid password. Please try again.
 authentication-required = Authentication Required
 cancel = Cancel
 authenticate = Authenticate
+details = Details
+polkit-action = Action
+polkit-vendor = Vendor
+polkit-requested-by = Requested by
+polkit-program = Program
+polkit-command-line = Command line
+polkit-unit = Unit
 log-out = Log Out
 suspend = Suspend
+hibernate = Hibernate
+hybrid-sleep = Hybrid sleep
+lock-screen = Lock screen
 restart = Restart
 enter-bios = Enter BIOS
 sound-settings = Sound settings
 shutdown = Shut down
+power-menu = Power
 headphones = Headphones
 headset = Headset
+caps-lock = Caps Lock { $enabled ->
+    [true] on
+    *[false] off
+}
+num-lock = Num Lock { $enabled ->
+    [true] on
+    *[false] off
+}
+charger-connected = Charger connected
+charger-disconnected = Charger disconnected
+time-until-full = { $hours } h { $minutes } min until full
+time-until-empty = { $hours } h { $minutes } min remaining
 confirm = Confirm
 confirm-button = {
     $action ->
         [restart] { restart }
         [suspend] { suspend }
+        [hibernate] { hibernate }
+        [hybrid-sleep] { hybrid-sleep }
+        [lock-screen] Lock
         [shutdown] Power off
         [log-out] { log-out }
         [enter-bios] { enter-bios }
@@ -24,19 +50,81 @@ confirm-title =
     { $action ->
         [restart] { restart } now?
         [suspend] { suspend } now?
+        [hibernate] { hibernate } now?
+        [hybrid-sleep] { hybrid-sleep } now?
+        [lock-screen] { lock-screen } now?
         [shutdown] { shutdown } now?
         [enter-bios] { enter-bios } now?
         [log-out] Quit all applications and log out now?
         [confirm-device-type] Confirm Device Type
         *[other] Apply the selected action now?
     }
+proceed-anyway = Proceed anyway
+inhibitor-weak = Doesn't stop this action
+confirm-blocked =
+    These apps are preventing the system from { $action ->
+        [restart] restarting
+        [suspend] suspending
+        [hibernate] hibernating
+        [hybrid-sleep] entering hybrid sleep
+        [shutdown] powering off
+        [log-out] logging out
+        [enter-bios] restarting into BIOS
+        *[other] applying the selected action
+    }. { $then ->
+        [perform] The countdown will continue once they finish.
+        [cancel] The countdown to close this dialog will continue once they finish.
+        *[wait] Wait for them to finish, or proceed anyway.
+    }
 confirm-body =
     The system will { $action ->
         [restart] restart
         [suspend] suspend
+        [hibernate] hibernate
+        [hybrid-sleep] enter hybrid sleep
         [shutdown] power off
         [lock-screen] lock the screen
         [log-out] log out
         [enter-bios] restart into BIOS
         *[other] apply the selected action
     } automatically in { $countdown } seconds.
+confirm-body-cancel = This dialog will close automatically in { $countdown } seconds.
+confirm-failed =
+    Couldn't { $action ->
+        [restart] restart
+        [suspend] suspend
+        [hibernate] hibernate
+        [hybrid-sleep] enter hybrid sleep
+        [shutdown] power off
+        [lock-screen] lock the screen
+        [log-out] log out
+        [enter-bios] restart into BIOS
+        *[other] apply the selected action
+    }: { $error }
+other-users =
+    { $count ->
+        [one] Another user is logged in, and will be logged out.
+        *[other] { $count } other users are logged in, and will be logged out.
+    }
+session-local = On { $location }
+session-remote = Remote
+session-remote-from = Remote, from { $host }
+requires-authentication = You'll be asked to authenticate first.
+confirm-unavailable =
+    { $action ->
+        [enter-bios] This system doesn't support restarting into BIOS.
+        *[other] This action isn't available on this system.
+    }
+scheduled-action =
+    { $action ->
+        [restart] Restarting
+        *[shutdown] Powering off
+    } in { $remaining }
+scheduled-cancel-failed = Couldn't cancel: { $error }
+boot-into = Restart into
+default-boot-entry = Default
+boot-menu = Boot menu
+updates-ready = Updates are ready to install.
+firmware-updates-ready = Firmware updates will be installed the next time the system starts.
+install-updates-restart = Install updates and restart
+install-updates-shutdown = Install updates and shut down
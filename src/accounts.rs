@@ -0,0 +1,102 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Users' real names and avatars, from AccountsService.
+
+use futures::{StreamExt, stream::BoxStream};
+use std::path::PathBuf;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{Connection, proxy};
+
+#[proxy(
+    interface = "org.freedesktop.Accounts",
+    default_service = "org.freedesktop.Accounts",
+    default_path = "/org/freedesktop/Accounts"
+)]
+trait Accounts {
+    fn find_user_by_id(&self, id: i64) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.Accounts.User",
+    default_service = "org.freedesktop.Accounts"
+)]
+trait AccountsUser {
+    #[zbus(property)]
+    fn real_name(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn icon_file(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn language(&self) -> zbus::Result<String>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct User {
+    pub uid: u32,
+    pub name: String,
+    /// The real name if set, else `name`
+    pub real_name: String,
+    pub icon: Option<PathBuf>,
+}
+
+/// Users with these UIDs, skipping any without a passwd entry. AccountsService may
+/// be missing, in which case users are shown by their login name.
+pub async fn users(connection: &Connection, uids: &[u32]) -> Vec<User> {
+    let mut users = Vec::new();
+    for &uid in uids {
+        let Some(name) =
+            uzers::get_user_by_uid(uid).and_then(|user| user.name().to_str().map(str::to_string))
+        else {
+            continue;
+        };
+        let mut user = User {
+            uid,
+            real_name: name.clone(),
+            name,
+            icon: None,
+        };
+        if let Err(err) = lookup(connection, &mut user).await {
+            log::debug!("Failed to look up user {} in AccountsService: {}", uid, err);
+        }
+        users.push(user);
+    }
+    users
+}
+
+/// The current user's language each time it's changed in Settings, like
+/// `de_DE.UTF-8`. Empty means the system default.
+pub async fn language_changes(connection: &Connection) -> zbus::Result<BoxStream<'static, String>> {
+    let account = account(connection, uzers::get_current_uid()).await?;
+    Ok(account
+        .receive_language_changed()
+        .await
+        // The first item repeats the current value
+        .skip(1)
+        .filter_map(|change| async move { change.get().await.ok() })
+        .boxed())
+}
+
+async fn account(connection: &Connection, uid: u32) -> zbus::Result<AccountsUserProxy<'static>> {
+    let path = AccountsProxy::new(connection)
+        .await?
+        .find_user_by_id(uid.into())
+        .await?;
+    AccountsUserProxy::builder(connection)
+        .path(path)?
+        .build()
+        .await
+}
+
+async fn lookup(connection: &Connection, user: &mut User) -> zbus::Result<()> {
+    let account = account(connection, user.uid).await?;
+
+    let real_name = account.real_name().await?;
+    if !real_name.is_empty() {
+        user.real_name = real_name;
+    }
+    // Points at a file that doesn't exist until an avatar is picked
+    user.icon = Some(PathBuf::from(account.icon_file().await?)).filter(|icon| icon.is_file());
+    Ok(())
+}
@@ -1,8 +1,16 @@
+use crate::components::scheduled_shutdown::{self, Schedule};
 use crate::components::{osd_indicator, polkit_dialog};
+use crate::config::{self, ActionPolicy, OnTimeout, OsdConfig, OutputPolicy};
 use crate::cosmic_session::CosmicSessionProxy;
 use crate::fl;
+use crate::inhibitors::{self, Inhibitor};
+use crate::logind_manager::LogindManagerProxy;
+use crate::logind_session::LogindSessionProxy;
 use crate::session_manager::SessionManagerProxy;
-use crate::subscriptions::{dbus, polkit_agent};
+use crate::sessions::{self, Session};
+use crate::subscriptions::{dbus, language, lock_keys, mpris, polkit_agent, upower};
+use crate::updates::{self, Updates};
+use chrono::{DateTime, Local};
 use clap::Parser;
 use cosmic::app::{CosmicFlags, Task};
 use cosmic::dbus_activation::Details;
@@ -17,38 +25,41 @@ use cosmic::iced::platform_specific::shell::commands::layer_surface::{
 use cosmic::iced::runtime::platform_specific::wayland::layer_surface::{
     IcedOutput, SctkLayerSurfaceSettings,
 };
-use cosmic::iced::widget::operation::focus;
+use cosmic::iced::widget::operation::{focus, focus_next, focus_previous};
 use cosmic::iced::window::Id as SurfaceId;
 use cosmic::iced::{self, Alignment, Length, Limits, Point, Rectangle, Size, Subscription, time};
 use cosmic::widget::{self, autosize, button, container, icon, text};
 use cosmic::{Apply, Element, theme};
 use cosmic_comp_config::input::TouchpadOverride;
+use cosmic_config::CosmicConfigEntry;
 use cosmic_settings_airplane_mode_subscription as airplane_mode;
 use cosmic_settings_audio_client::{self as audio_client, CosmicAudioProxy};
 use cosmic_settings_daemon_subscription as settings_daemon;
 use cosmic_settings_upower_subscription::kbdbacklight::{
-    KeyboardBacklightUpdate, kbd_backlight_subscription,
+    KeyboardBacklightRequest, KeyboardBacklightUpdate, kbd_backlight_subscription,
 };
+use i18n_embed::unic_langid::LanguageIdentifier;
 use logind_zbus::manager::ManagerProxy;
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::any::TypeId;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::process::Stdio;
-use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
 use zbus::Connection;
 
 // Type alias for Wayland output. Matches what's used in SctkLayerSurfaceSettings
 type WlOutput = cosmic::cctk::sctk::reexports::client::protocol::wl_output::WlOutput;
 
-const COUNTDOWN_LENGTH: u8 = 60;
 static CONFIRM_ID: LazyLock<iced::id::Id> = LazyLock::new(|| iced::id::Id::new("confirm-id"));
 static CANCEL_ID: LazyLock<iced::id::Id> = LazyLock::new(|| iced::id::Id::new("cancel-id"));
 static AUTOSIZE_DIALOG_ID: LazyLock<iced::id::Id> =
     LazyLock::new(|| iced::id::Id::new("autosize-id"));
+static AUTOSIZE_POWER_MENU_ID: LazyLock<iced::id::Id> =
+    LazyLock::new(|| iced::id::Id::new("autosize-power-menu-id"));
 
 #[derive(Parser, Debug, Serialize, Deserialize, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -69,13 +80,51 @@ pub enum OsdTask {
     #[clap(about = "Toggle the on screen display and start the log out timer")]
     LogOut,
     #[clap(about = "Toggle the on screen display and start the restart timer")]
-    Restart,
+    Restart {
+        #[command(flatten)]
+        schedule: Schedule,
+        #[arg(
+            long,
+            conflicts_with_all = ["delay", "at"],
+            help = "Install prepared updates while restarting"
+        )]
+        #[serde(default)]
+        install_updates: bool,
+    },
     #[clap(about = "Toggle the on screen display and start the shutdown timer")]
-    Shutdown,
+    Shutdown {
+        #[command(flatten)]
+        schedule: Schedule,
+        #[arg(
+            long,
+            conflicts_with_all = ["delay", "at"],
+            help = "Install prepared updates while shutting down"
+        )]
+        #[serde(default)]
+        install_updates: bool,
+    },
     #[clap(about = "Display touchpad toggle indicator")]
     Touchpad,
     #[clap(about = "Toggle the on screen display and start the restart to bios timer")]
     EnterBios,
+    #[clap(about = "Toggle the on screen display and start the restart to boot entry timer")]
+    RebootTo {
+        #[arg(
+            long,
+            help = "Boot loader entry ID. Without it, the boot loader menu is shown."
+        )]
+        entry: Option<String>,
+    },
+    #[clap(about = "Toggle the on screen display and start the suspend timer")]
+    Suspend,
+    #[clap(about = "Toggle the on screen display and start the hibernate timer")]
+    Hibernate,
+    #[clap(about = "Toggle the on screen display and start the hybrid sleep timer")]
+    HybridSleep,
+    #[clap(about = "Lock the screen, after confirmation if configured")]
+    LockScreen,
+    #[clap(about = "Show a menu of power actions, each confirmed as usual")]
+    PowerMenu,
     ConfirmHeadphones {
         #[arg(long)]
         device: u32,
@@ -85,13 +134,113 @@ pub enum OsdTask {
 }
 
 impl OsdTask {
+    /// How the action is confirmed, per the config
+    fn policy(&self, actions: &config::Actions) -> ActionPolicy {
+        match self {
+            OsdTask::LogOut => actions.log_out,
+            OsdTask::Restart { .. } => actions.restart,
+            OsdTask::Shutdown { .. } => actions.shutdown,
+            OsdTask::EnterBios => actions.enter_bios,
+            OsdTask::RebootTo { .. } => actions.restart,
+            OsdTask::Suspend => actions.suspend,
+            OsdTask::Hibernate => actions.hibernate,
+            OsdTask::HybridSleep => actions.hybrid_sleep,
+            OsdTask::LockScreen => actions.lock_screen,
+            OsdTask::ConfirmHeadphones { .. } => ActionPolicy::default(),
+            OsdTask::Display
+            | OsdTask::IdentifyDisplays
+            | OsdTask::DismissDisplayIdentifiers
+            | OsdTask::Touchpad
+            | OsdTask::PowerMenu => ActionPolicy {
+                confirm: false,
+                ..ActionPolicy::default()
+            },
+        }
+    }
+
+    /// Which inhibitors can block the action
+    fn inhibit_kind(&self) -> Option<inhibitors::Kind> {
+        match self {
+            OsdTask::LogOut => Some(inhibitors::Kind::LogOut),
+            OsdTask::Restart { .. }
+            | OsdTask::Shutdown { .. }
+            | OsdTask::EnterBios
+            | OsdTask::RebootTo { .. } => Some(inhibitors::Kind::Shutdown),
+            OsdTask::Suspend | OsdTask::Hibernate | OsdTask::HybridSleep => {
+                Some(inhibitors::Kind::Sleep)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this is a shutdown or restart given `--in` or `--at`
+    fn is_scheduled(&self) -> bool {
+        matches!(
+            self,
+            OsdTask::Restart { schedule, .. } | OsdTask::Shutdown { schedule, .. }
+                if schedule.is_set()
+        )
+    }
+
+    /// When a shutdown or restart given `--in` or `--at` is scheduled for
+    fn scheduled(&self) -> Result<Option<(scheduled_shutdown::Kind, DateTime<Local>)>, String> {
+        let (kind, schedule) = match self {
+            OsdTask::Restart { schedule, .. } => (scheduled_shutdown::Kind::Reboot, schedule),
+            OsdTask::Shutdown { schedule, .. } => (scheduled_shutdown::Kind::PowerOff, schedule),
+            _ => return Ok(None),
+        };
+        Ok(schedule.time()?.map(|at| (kind, at)))
+    }
+
+    /// Run the action without a dialog, asking logind first whether it needs to
+    /// ask for authentication
     fn perform(self) -> Task<Msg> {
-        let msg = |m| cosmic::action::app(Msg::Zbus(m));
+        if !matches!(
+            self,
+            OsdTask::EnterBios
+                | OsdTask::RebootTo { .. }
+                | OsdTask::Restart { .. }
+                | OsdTask::Shutdown { .. }
+                | OsdTask::Suspend
+                | OsdTask::Hibernate
+                | OsdTask::HybridSleep
+        ) {
+            return self.perform_with(false, Msg::Zbus);
+        }
+        cosmic::task::future(Capabilities::query()).then(move |capabilities| {
+            self.clone()
+                .perform_with(capabilities.interactive(&self), Msg::Zbus)
+        })
+    }
+
+    /// Run the action, reporting how it went with `on_result`. `interactive` lets
+    /// logind ask for authentication when its policy requires it.
+    fn perform_with(
+        self,
+        interactive: bool,
+        on_result: impl Fn(zbus::Result<()>) -> Msg + Send + 'static,
+    ) -> Task<Msg> {
+        let msg = move |m| cosmic::action::app(on_result(m));
         match self {
-            OsdTask::EnterBios => cosmic::task::future(restart(true)).map(msg),
+            OsdTask::EnterBios => cosmic::task::future(restart(true, interactive)).map(msg),
+            OsdTask::RebootTo { entry } => {
+                cosmic::task::future(reboot_to(entry, interactive)).map(msg)
+            }
             OsdTask::LogOut => cosmic::task::future(log_out()).map(msg),
-            OsdTask::Restart => cosmic::task::future(restart(false)).map(msg),
-            OsdTask::Shutdown => cosmic::task::future(shutdown()).map(msg),
+            OsdTask::Restart {
+                install_updates: true,
+                ..
+            } => cosmic::task::future(install_updates_and(false, interactive)).map(msg),
+            OsdTask::Restart { .. } => cosmic::task::future(restart(false, interactive)).map(msg),
+            OsdTask::Shutdown {
+                install_updates: true,
+                ..
+            } => cosmic::task::future(install_updates_and(true, interactive)).map(msg),
+            OsdTask::Shutdown { .. } => cosmic::task::future(shutdown(interactive)).map(msg),
+            OsdTask::Suspend => cosmic::task::future(suspend(interactive)).map(msg),
+            OsdTask::Hibernate => cosmic::task::future(hibernate(interactive)).map(msg),
+            OsdTask::HybridSleep => cosmic::task::future(hybrid_sleep(interactive)).map(msg),
+            OsdTask::LockScreen => cosmic::task::future(lock_screen()).map(msg),
             OsdTask::ConfirmHeadphones {
                 device,
                 selected_headset,
@@ -108,6 +257,7 @@ impl OsdTask {
                 Task::none()
             }
             OsdTask::Touchpad => Task::none(),
+            OsdTask::PowerMenu => Task::none(),
             OsdTask::Display => Task::none(),
             OsdTask::IdentifyDisplays => Task::none(),
             OsdTask::DismissDisplayIdentifiers => Task::none(),
@@ -115,19 +265,230 @@ impl OsdTask {
     }
 }
 
-async fn restart(reboot_to_firmware_setup: bool) -> zbus::Result<()> {
+async fn restart(reboot_to_firmware_setup: bool, interactive: bool) -> zbus::Result<()> {
     let connection = Connection::system().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
-    _ = manager_proxy
+    let result = manager_proxy
         .set_reboot_to_firmware_setup(reboot_to_firmware_setup)
         .await;
-    manager_proxy.reboot(true).await
+    // A normal restart only clears the flag, which can't fail in a way that matters
+    if reboot_to_firmware_setup {
+        result?;
+    }
+    manager_proxy.reboot(interactive).await
+}
+
+/// Hand a shutdown or restart given `--in` or `--at` to logind. Failures open the
+/// dialog to show why, and never run the action right away instead.
+fn schedule(action: OsdTask) -> Task<Msg> {
+    let (kind, at) = match action.scheduled() {
+        Ok(Some(scheduled)) => scheduled,
+        Ok(None) => return Task::none(),
+        Err(err) => return Task::done(cosmic::action::app(Msg::ScheduleFailed(action, err))),
+    };
+    let query = action.clone();
+    cosmic::task::future(async move {
+        // logind checks the same authorizations as for powering off right away
+        let interactive = Capabilities::query().await.interactive(&query);
+        scheduled_shutdown::schedule(kind, at, interactive).await
+    })
+    .map(move |result| {
+        cosmic::action::app(match result {
+            Ok(()) => Msg::ShutdownScheduled(kind, at),
+            Err(err) => Msg::ScheduleFailed(action.clone(), err.to_string()),
+        })
+    })
+}
+
+/// Restart or power off, installing a prepared offline update on the way. PackageKit
+/// installs it while booting and powers off afterwards if asked, so both restart
+/// when there is one. Staged firmware is installed by the next boot either way.
+async fn install_updates_and(power_off: bool, interactive: bool) -> zbus::Result<()> {
+    if updates::install_offline(power_off).await? || !power_off {
+        restart(false, interactive).await
+    } else {
+        shutdown(interactive).await
+    }
 }
 
-async fn shutdown() -> zbus::Result<()> {
+/// Whether logind allows an action, from its `Can*` methods. Ordered from most to
+/// least allowed, so actions needing several calls take the `max`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Capability {
+    #[default]
+    Yes,
+    /// Allowed after authenticating
+    Challenge,
+    No,
+}
+
+impl From<&str> for Capability {
+    fn from(answer: &str) -> Self {
+        match answer {
+            "yes" => Capability::Yes,
+            "challenge" => Capability::Challenge,
+            // "no" and "na"
+            _ => Capability::No,
+        }
+    }
+}
+
+/// What logind allows, checked before the confirmation dialog opens
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Capabilities {
+    power_off: Capability,
+    reboot: Capability,
+    suspend: Capability,
+    hibernate: Capability,
+    hybrid_sleep: Capability,
+    reboot_to_firmware_setup: Capability,
+    reboot_to_boot_loader_entry: Capability,
+    reboot_to_boot_loader_menu: Capability,
+    /// logind answered, so the capabilities above aren't just defaults
+    queried: bool,
+}
+
+impl Capabilities {
+    async fn query() -> Self {
+        let logind_proxy = async {
+            let connection = Connection::system().await?;
+            LogindManagerProxy::new(&connection).await
+        };
+        let logind_proxy = match logind_proxy.await {
+            Ok(logind_proxy) => logind_proxy,
+            Err(err) => {
+                // Each action reports its own error if it fails
+                log::debug!("Failed to check which power actions are allowed: {}", err);
+                return Self::default();
+            }
+        };
+        let answer = |result: zbus::Result<String>| {
+            result.map_or(Capability::Yes, |answer| Capability::from(answer.as_str()))
+        };
+
+        Self {
+            power_off: answer(logind_proxy.can_power_off().await),
+            reboot: answer(logind_proxy.can_reboot().await),
+            suspend: answer(logind_proxy.can_suspend().await),
+            hibernate: answer(logind_proxy.can_hibernate().await),
+            hybrid_sleep: answer(logind_proxy.can_hybrid_sleep().await),
+            reboot_to_firmware_setup: answer(logind_proxy.can_reboot_to_firmware_setup().await),
+            reboot_to_boot_loader_entry: answer(
+                logind_proxy.can_reboot_to_boot_loader_entry().await,
+            ),
+            reboot_to_boot_loader_menu: answer(logind_proxy.can_reboot_to_boot_loader_menu().await),
+            queried: true,
+        }
+    }
+
+    /// Whether logind should be allowed to ask for authentication before running
+    /// `action`. Without it, logind refuses actions that need authentication, so
+    /// it's also allowed when what logind allows isn't known.
+    fn interactive(&self, action: &OsdTask) -> bool {
+        !self.queried || self.of(action) == Capability::Challenge
+    }
+
+    fn of(&self, action: &OsdTask) -> Capability {
+        match action {
+            // Installing a prepared offline update restarts first
+            OsdTask::Shutdown {
+                install_updates: true,
+                ..
+            } => self.power_off.max(self.reboot),
+            OsdTask::Shutdown { .. } => self.power_off,
+            OsdTask::Restart { .. } => self.reboot,
+            OsdTask::Suspend => self.suspend,
+            OsdTask::Hibernate => self.hibernate,
+            OsdTask::HybridSleep => self.hybrid_sleep,
+            OsdTask::EnterBios => self.reboot.max(self.reboot_to_firmware_setup),
+            OsdTask::RebootTo { entry: Some(_) } => {
+                self.reboot.max(self.reboot_to_boot_loader_entry)
+            }
+            OsdTask::RebootTo { entry: None } => self.reboot.max(self.reboot_to_boot_loader_menu),
+            _ => Capability::Yes,
+        }
+    }
+}
+
+async fn reboot_to(entry: Option<String>, interactive: bool) -> zbus::Result<()> {
     let connection = Connection::system().await?;
+    let logind_proxy = LogindManagerProxy::new(&connection).await?;
+    match entry {
+        Some(entry) => logind_proxy.set_reboot_to_boot_loader_entry(&entry).await?,
+        // Show the menu until an entry is picked. A timeout of `u64::MAX` would
+        // clear the request instead.
+        None => logind_proxy.set_reboot_to_boot_loader_menu(0).await?,
+    }
     let manager_proxy = ManagerProxy::new(&connection).await?;
-    manager_proxy.power_off(true).await
+    manager_proxy.reboot(interactive).await
+}
+
+/// Boot loader entries worth offering in the restart dialog
+async fn boot_loader_entries() -> Vec<String> {
+    let entries = async {
+        let connection = Connection::system().await?;
+        LogindManagerProxy::new(&connection)
+            .await?
+            .boot_loader_entries()
+            .await
+    };
+    match entries.await {
+        // These duplicate other actions
+        Ok(entries) => entries
+            .into_iter()
+            .filter(|entry| {
+                !matches!(
+                    entry.as_str(),
+                    "auto-reboot-to-firmware-setup" | "auto-poweroff" | "auto-reboot"
+                )
+            })
+            .collect(),
+        Err(err) => {
+            log::debug!("Failed to list boot loader entries: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Name for a boot loader entry ID, like `arch.conf` or systemd-boot's `auto-windows`
+fn boot_entry_name(entry: &str) -> String {
+    match entry {
+        "auto-windows" => "Windows".to_string(),
+        "auto-osx" => "macOS".to_string(),
+        "auto-efi-shell" => "EFI Shell".to_string(),
+        "auto-efi-default" => "EFI Default Loader".to_string(),
+        _ => entry.strip_suffix(".conf").unwrap_or(entry).to_string(),
+    }
+}
+
+async fn shutdown(interactive: bool) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.power_off(interactive).await
+}
+
+async fn suspend(interactive: bool) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.suspend(interactive).await
+}
+
+async fn hibernate(interactive: bool) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.hibernate(interactive).await
+}
+
+async fn hybrid_sleep(interactive: bool) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.hybrid_sleep(interactive).await
+}
+
+async fn lock_screen() -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let session_proxy = LogindSessionProxy::new(&connection).await?;
+    session_proxy.lock().await
 }
 
 async fn log_out() -> zbus::Result<()> {
@@ -180,24 +541,46 @@ pub enum DisplayMode {
 pub enum Msg {
     Action(OsdTask),
     AudioClient(super::audio::Message),
+    BootEntry(usize),
+    BootLoaderEntries(SurfaceId, Vec<String>),
     Confirm,
+    Confirmation(OsdTask, Capabilities),
+    Capabilities(SurfaceId, Capabilities),
     Cancel,
     Countdown,
     DBus(dbus::Event),
     Display(Option<DisplayMode>),
     Focused,
+    FocusNext,
+    FocusPrevious,
+    Unfocused(SurfaceId),
     Headphones(bool),
+    Inhibitors(SurfaceId, Vec<Inhibitor>),
+    InhibitorsChanged,
+    InstallUpdates(bool),
+    LockKey(lock_keys::Event),
+    Media(mpris::Media),
+    Language(Vec<LanguageIdentifier>),
     PolkitAgent(polkit_agent::Event),
     PolkitDialog((SurfaceId, polkit_dialog::Msg)),
+    Power(upower::Power),
+    ScheduledShutdown((SurfaceId, scheduled_shutdown::Msg)),
+    ScheduleFailed(OsdTask, String),
+    ShutdownScheduled(scheduled_shutdown::Kind, DateTime<Local>),
     SettingsDaemon(settings_daemon::Event),
-    OsdIndicator(osd_indicator::Msg),
+    OsdIndicator((SurfaceId, osd_indicator::Msg)),
     AirplaneMode(bool),
     KeyboardBacklight(KeyboardBacklightUpdate),
+    OtherSessions(SurfaceId, Vec<Session>),
     Overlap(OverlapNotifyEvent),
+    PowerMenu(Capabilities),
+    PowerMenuSelect(OsdTask),
+    Performed(SurfaceId, Result<(), zbus::Error>),
     Size(Size),
     Zbus(Result<(), zbus::Error>),
     SoundSettings,
     TouchpadEnabled(Option<TouchpadOverride>),
+    Updates(SurfaceId, Updates),
     ActivationToken(Option<String>),
     DisplayIdentifierSurface((SurfaceId, osd_indicator::Msg)),
     ResetDisplayIdentifierTimer(SurfaceId),
@@ -205,11 +588,75 @@ pub enum Msg {
     DismissDisplayIdentifiers,
     OutputInfo(WlOutput, String),
     OutputRemoved(WlOutput),
+    PointerEntered(SurfaceId),
+    Config(OsdConfig),
+}
+
+/// Action waiting in the confirmation dialog
+struct Confirmation {
+    id: SurfaceId,
+    action: OsdTask,
+    policy: ActionPolicy,
+    /// Seconds left until `policy.on_timeout` applies
+    countdown: u32,
+    /// Apps blocking the action. The countdown is paused while there are any.
+    inhibitors: Vec<Inhibitor>,
+    /// Other users who would be logged out by a shutdown or restart
+    other_sessions: Vec<Session>,
+    /// Boot loader entry IDs a restart can boot into
+    boot_entries: Vec<String>,
+    /// Options of the boot entry picker: the default entry, the menu, then `boot_entries`
+    boot_entry_names: Vec<String>,
+    /// Updates a shutdown or restart can install
+    updates: Updates,
+    capabilities: Capabilities,
+    /// Set once confirmed, until the action reports back
+    performing: bool,
+    /// Why the action failed, shown in place of the countdown
+    error: Option<String>,
+}
+
+/// Menu of power actions, each confirmed as usual once picked
+struct PowerMenu {
+    id: SurfaceId,
+    capabilities: Capabilities,
+}
+
+impl Confirmation {
+    fn capability(&self) -> Capability {
+        self.capabilities.of(&self.action)
+    }
+
+    /// Whether an app is stopping the action, apart from weak inhibitors
+    fn blocked(&self) -> bool {
+        self.inhibitors.iter().any(|inhibitor| !inhibitor.weak)
+    }
+
+    /// Other users' sessions need the stricter `*-multiple-sessions` authorization,
+    /// which logind can only ask for interactively
+    fn interactive(&self) -> bool {
+        self.capabilities.interactive(&self.action) || !self.other_sessions.is_empty()
+    }
+
+    /// Index of the action in the boot entry picker
+    fn boot_entry(&self) -> Option<usize> {
+        match &self.action {
+            OsdTask::Restart { .. } => Some(0),
+            OsdTask::RebootTo { entry: None } => Some(1),
+            OsdTask::RebootTo { entry: Some(entry) } => self
+                .boot_entries
+                .iter()
+                .position(|e| e == entry)
+                .map(|pos| pos + 2),
+            _ => None,
+        }
+    }
 }
 
 enum Surface {
     PolkitDialog(polkit_dialog::State),
     OsdIndicator(osd_indicator::State),
+    ScheduledShutdown(scheduled_shutdown::State),
 }
 
 struct App {
@@ -217,47 +664,422 @@ struct App {
     connection: Option<zbus::Connection>,
     system_connection: Option<zbus::Connection>,
     surfaces: HashMap<SurfaceId, Surface>,
-    indicator: Option<(SurfaceId, osd_indicator::State)>,
+    indicators: HashMap<SurfaceId, osd_indicator::State>,
+    settings_daemon_sender: Option<UnboundedSender<settings_daemon::Request>>,
     max_display_brightness: Option<i32>,
     display_brightness: Option<i32>,
+    keyboard_backlight_sender: Option<UnboundedSender<KeyboardBacklightRequest>>,
+    polkit_languages_sender: Option<UnboundedSender<Vec<LanguageIdentifier>>>,
     max_keyboard_brightness: Option<i32>,
     keyboard_brightness: Option<i32>,
     audio: super::audio::model::Model,
-    audio_client: Option<Rc<RefCell<audio_client::Client>>>,
+    audio_client: Option<Arc<tokio::sync::Mutex<audio_client::Client>>>,
     sink_last_playback: Instant,
     airplane_mode: Option<bool>,
     overlap: HashMap<String, Rectangle>,
     size: Option<Size>,
-    action_to_confirm: Option<(SurfaceId, OsdTask, u8)>,
+    action_to_confirm: Option<Confirmation>,
+    power_menu: Option<PowerMenu>,
     wayland_outputs: HashMap<String, (WlOutput, String)>,
+    /// Output of the surface the pointer last entered, for [`OutputPolicy::Pointer`]
+    pointer_output: Option<WlOutput>,
     display_identifier_displays: HashMap<SurfaceId, String>,
     identifiers_dismissed: bool,
+    config: OsdConfig,
 }
 
 impl App {
     fn create_indicator(&mut self, params: osd_indicator::Params) -> cosmic::app::Task<Msg> {
-        if let Some((_id, state)) = &mut self.indicator {
-            state.replace_params(params)
+        if self.indicators.is_empty() {
+            return self.replace_indicators(params);
+        }
+
+        let tasks = self.indicators.iter_mut().map(|(id, state)| {
+            let id = *id;
+            state
+                .replace_params(params.clone())
+                .map(move |x| cosmic::Action::App(Msg::OsdIndicator((id, x))))
+        });
+        iced::Task::batch(tasks.collect::<Vec<_>>())
+    }
+
+    /// Destroy any visible indicators and show `params` on new surfaces,
+    /// one for each output selected by the output policy.
+    fn replace_indicators(&mut self, params: osd_indicator::Params) -> cosmic::app::Task<Msg> {
+        let mut tasks = Vec::new();
+        for (id, _) in self.indicators.drain() {
+            tasks.push(destroy_layer_surface(id));
+        }
+        self.overlap.clear();
+
+        for output in self.indicator_outputs() {
+            let id = SurfaceId::unique();
+            let (state, cmd) =
+                osd_indicator::State::new_with_output(id, params.clone(), output, &self.config);
+            self.indicators.insert(id, state);
+            tasks.push(cmd.map(move |x| cosmic::Action::App(Msg::OsdIndicator((id, x)))));
+        }
+
+        iced::Task::batch(tasks)
+    }
+
+    /// Apply a value picked on an interactive indicator to the device it shows.
+    fn set_indicator_value(&self, params: &osd_indicator::Params, value: u32) -> Task<Msg> {
+        match params {
+            osd_indicator::Params::SinkVolume(..) | osd_indicator::Params::SourceVolume(..) => {
+                let node_id = if let osd_indicator::Params::SinkVolume(..) = params {
+                    self.audio.default_sink()
+                } else {
+                    self.audio.default_source()
+                };
+                let (Some(client), Some(node_id)) = (self.audio_client.clone(), node_id) else {
+                    return Task::none();
+                };
+                cosmic::task::future(async move {
+                    let mut client = client.lock().await;
+                    client.conn.set_node_volume(node_id, value).await
+                })
+                .map(|result| cosmic::action::app(Msg::Zbus(result)))
+            }
+            osd_indicator::Params::DisplayBrightness(_)
+            | osd_indicator::Params::DisplayBrightnessExact(_) => {
+                let (Some(sender), Some(max)) =
+                    (&self.settings_daemon_sender, self.max_display_brightness)
+                else {
+                    return Task::none();
+                };
+                let brightness = if max <= 20 {
+                    // Coarse displays: inverse of rung_ratio=(raw+1)/20
+                    (value as f64 / 5.0).round() as i32 - 1
+                } else {
+                    // Never turn the backlight off from the OSD
+                    ((value as f64 / 100.0 * max as f64).round() as i32).max(1)
+                };
+                _ = sender.send(settings_daemon::Request::SetDisplayBrightness(
+                    brightness.clamp(0, max),
+                ));
+                Task::none()
+            }
+            osd_indicator::Params::KeyboardBrightness(_) => {
+                let (Some(sender), Some(max)) = (
+                    &self.keyboard_backlight_sender,
+                    self.max_keyboard_brightness,
+                ) else {
+                    return Task::none();
+                };
+                let brightness = (value as f64 / 100.0 * max as f64).round() as i32;
+                _ = sender.send(KeyboardBacklightRequest::Set(brightness.clamp(0, max)));
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Show the confirmation dialog for `action`, or replace the action of the one
+    /// already shown. Actions configured to skip confirmation run immediately, and
+    /// scheduled ones are handed to logind.
+    fn confirm_action(&mut self, action: OsdTask) -> cosmic::app::Task<Msg> {
+        if matches!(action, OsdTask::PowerMenu) {
+            return cosmic::task::future(async { Msg::PowerMenu(Capabilities::query().await) })
+                .map(cosmic::Action::App);
+        }
+
+        if action.is_scheduled() {
+            return schedule(action);
+        }
+
+        let policy = action.policy(&self.config.actions);
+        if !policy.confirm {
+            return action.perform();
+        }
+
+        // Check first, so the dialog never offers what logind would refuse
+        cosmic::task::future(async move { Msg::Confirmation(action, Capabilities::query().await) })
+            .map(cosmic::Action::App)
+    }
+
+    /// Open the confirmation dialog, once what logind allows is known
+    fn show_confirmation(
+        &mut self,
+        action: OsdTask,
+        capabilities: Capabilities,
+    ) -> cosmic::app::Task<Msg> {
+        let policy = action.policy(&self.config.actions);
+        let kind = action.inhibit_kind();
+        if let Some(confirmation) = self.action_to_confirm.as_mut() {
+            confirmation.action = action;
+            confirmation.policy = policy;
+            confirmation.countdown = policy.countdown;
+            confirmation.inhibitors.clear();
+            confirmation.other_sessions.clear();
+            confirmation.capabilities = capabilities;
+            confirmation.performing = false;
+            confirmation.error = None;
+            let id = confirmation.id;
+            Task::batch([
+                self.query_inhibitors(id, kind),
+                self.query_boot_loader_entries(id),
+                self.query_other_sessions(id, kind),
+                self.query_updates(id),
+            ])
         } else {
-            let mut cmds = Vec::new();
             let id = SurfaceId::unique();
-            self.overlap.clear();
-            let (state, cmd) = osd_indicator::State::new(id, params);
+            self.action_to_confirm = Some(Confirmation {
+                id,
+                action,
+                policy,
+                countdown: policy.countdown,
+                inhibitors: Vec::new(),
+                other_sessions: Vec::new(),
+                boot_entries: Vec::new(),
+                boot_entry_names: Vec::new(),
+                updates: Updates::default(),
+                capabilities,
+                performing: false,
+                error: None,
+            });
+            Task::batch([
+                get_layer_surface(SctkLayerSurfaceSettings {
+                    id,
+                    keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                    anchor: Anchor::empty(),
+                    namespace: "dialog".into(),
+                    size: None,
+                    size_limits: Limits::NONE.min_width(1.0).min_height(1.0),
+                    ..Default::default()
+                }),
+                self.query_inhibitors(id, kind),
+                self.query_boot_loader_entries(id),
+                self.query_other_sessions(id, kind),
+                self.query_updates(id),
+            ])
+        }
+    }
+
+    /// Run the confirmed action. The dialog stays open until it reports back, so a
+    /// failure can be shown instead of the dialog just disappearing.
+    fn perform_confirmed(&mut self) -> cosmic::app::Task<Msg> {
+        let Some(confirmation) = self.action_to_confirm.as_mut() else {
+            return Task::none();
+        };
+        if confirmation.capability() == Capability::No || confirmation.performing {
+            return Task::none();
+        }
+        if matches!(confirmation.action, OsdTask::ConfirmHeadphones { .. }) {
+            let Confirmation { id, action, .. } = self.action_to_confirm.take().unwrap();
+            return Task::batch(vec![destroy_layer_surface(id), action.perform()]);
+        }
+
+        confirmation.performing = true;
+        confirmation.error = None;
+        // Retrying a schedule that failed
+        if confirmation.action.is_scheduled() {
+            return schedule(confirmation.action.clone());
+        }
+        let id = confirmation.id;
+        confirmation
+            .action
+            .clone()
+            .perform_with(confirmation.interactive(), move |result| {
+                Msg::Performed(id, result)
+            })
+    }
+
+    fn show_power_menu(&mut self, capabilities: Capabilities) -> cosmic::app::Task<Msg> {
+        if let Some(menu) = self.power_menu.as_mut() {
+            menu.capabilities = capabilities;
+            return Task::none();
+        }
+
+        let id = SurfaceId::unique();
+        self.power_menu = Some(PowerMenu { id, capabilities });
+        get_layer_surface(SctkLayerSurfaceSettings {
+            id,
+            keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            anchor: Anchor::empty(),
+            namespace: "dialog".into(),
+            size: None,
+            size_limits: Limits::NONE.min_width(1.0).min_height(1.0),
+            ..Default::default()
+        })
+    }
 
-            if let Some(old) = self.indicator.replace((id, state)) {
-                cmds.push(destroy_layer_surface(old.0));
+    fn view_power_menu(&self, menu: &PowerMenu) -> Element<'_, Msg> {
+        let cosmic_theme = self.core.system_theme().cosmic();
+        let items = [
+            (
+                OsdTask::LockScreen,
+                "system-lock-screen-symbolic",
+                fl!("lock-screen"),
+            ),
+            (OsdTask::LogOut, "system-log-out-symbolic", fl!("log-out")),
+            (OsdTask::Suspend, "system-suspend-symbolic", fl!("suspend")),
+            (
+                OsdTask::Restart {
+                    schedule: Schedule::default(),
+                    install_updates: false,
+                },
+                "system-restart-symbolic",
+                fl!("restart"),
+            ),
+            (
+                OsdTask::Shutdown {
+                    schedule: Schedule::default(),
+                    install_updates: false,
+                },
+                "system-shutdown-symbolic",
+                fl!("shutdown"),
+            ),
+            (
+                OsdTask::EnterBios,
+                "system-restart-symbolic",
+                fl!("enter-bios"),
+            ),
+        ];
+
+        let choices = items.into_iter().map(|(action, icon_name, label)| {
+            let capability = menu.capabilities.of(&action);
+            let mut children = vec![text_icon(icon_name, 24).into(), text::body(label).into()];
+            if capability == Capability::Challenge {
+                children.push(widget::space::horizontal().into());
+                children.push(text_icon("dialog-password-symbolic", 16).into());
             }
-            cmds.push(cmd);
+            button::custom(
+                widget::row::with_children(children)
+                    .spacing(cosmic_theme.space_s())
+                    .align_y(Alignment::Center),
+            )
+            .width(Length::Fill)
+            .padding([cosmic_theme.space_xs(), cosmic_theme.space_s()])
+            .class(theme::Button::Text)
+            .on_press_maybe((capability != Capability::No).then_some(Msg::PowerMenuSelect(action)))
+            .into()
+        });
+
+        let dialog = widget::dialog()
+            .title(fl!("power-menu"))
+            .control(
+                widget::column::with_children(choices)
+                    .spacing(cosmic_theme.space_xxxs())
+                    .width(Length::Fixed(320.)),
+            )
+            .secondary_action(
+                button::custom(min_width_and_height(
+                    text::body(fl!("cancel")).into(),
+                    142.0,
+                    32.0,
+                ))
+                .padding([0, cosmic_theme.space_s()])
+                .class(theme::Button::Standard)
+                .on_press(Msg::Cancel),
+            );
 
-            iced::Task::batch(cmds)
+        Element::from(
+            autosize::autosize(container(dialog), AUTOSIZE_POWER_MENU_ID.clone()).limits(
+                Limits::NONE
+                    .min_width(1.)
+                    .min_height(1.)
+                    .max_width(900.)
+                    .max_height(900.),
+            ),
+        )
+    }
+
+    fn query_other_sessions(
+        &self,
+        id: SurfaceId,
+        kind: Option<inhibitors::Kind>,
+    ) -> cosmic::app::Task<Msg> {
+        if kind != Some(inhibitors::Kind::Shutdown) {
+            return Task::none();
         }
-        .map(|x| cosmic::Action::App(Msg::OsdIndicator(x)))
+        cosmic::task::future(sessions::others())
+            .map(move |sessions| cosmic::action::app(Msg::OtherSessions(id, sessions)))
     }
 
-    fn handle_overlap(&mut self) {
-        let Some((_, state)) = self.indicator.as_mut() else {
-            return;
+    fn query_boot_loader_entries(&self, id: SurfaceId) -> cosmic::app::Task<Msg> {
+        let Some(confirmation) = self.action_to_confirm.as_ref() else {
+            return Task::none();
         };
+        if confirmation.boot_entry().is_none() || !confirmation.boot_entries.is_empty() {
+            return Task::none();
+        }
+        cosmic::task::future(boot_loader_entries())
+            .map(move |entries| cosmic::action::app(Msg::BootLoaderEntries(id, entries)))
+    }
+
+    fn query_updates(&self, id: SurfaceId) -> cosmic::app::Task<Msg> {
+        let Some(confirmation) = self.action_to_confirm.as_ref() else {
+            return Task::none();
+        };
+        if !matches!(
+            confirmation.action,
+            OsdTask::Restart { .. } | OsdTask::Shutdown { .. }
+        ) {
+            return Task::none();
+        }
+        cosmic::task::future(updates::pending())
+            .map(move |updates| cosmic::action::app(Msg::Updates(id, updates)))
+    }
+
+    fn query_inhibitors(
+        &self,
+        id: SurfaceId,
+        kind: Option<inhibitors::Kind>,
+    ) -> cosmic::app::Task<Msg> {
+        let (Some(kind), Some(system)) = (kind, self.system_connection.clone()) else {
+            return Task::none();
+        };
+        cosmic::task::future(inhibitors::blocking(kind, system, self.connection.clone()))
+            .map(move |list| cosmic::action::app(Msg::Inhibitors(id, list)))
+    }
+
+    fn indicator_outputs(&self) -> Vec<IcedOutput> {
+        match self.config.output {
+            OutputPolicy::Active => vec![IcedOutput::Active],
+            OutputPolicy::Pointer => vec![
+                self.pointer_output
+                    .clone()
+                    .map_or(IcedOutput::Active, IcedOutput::Output),
+            ],
+            OutputPolicy::Primary => {
+                vec![self.primary_output().map_or(IcedOutput::Active, |output| {
+                    IcedOutput::Output(output.clone())
+                })]
+            }
+            OutputPolicy::All if self.wayland_outputs.is_empty() => vec![IcedOutput::Active],
+            OutputPolicy::All => self
+                .wayland_outputs
+                .values()
+                .map(|(output, _)| IcedOutput::Output(output.clone()))
+                .collect(),
+        }
+    }
+
+    /// The configured primary output, or the first internal display if it isn't connected.
+    fn primary_output(&self) -> Option<&WlOutput> {
+        if let Some((output, _)) = self
+            .config
+            .primary_output
+            .as_ref()
+            .and_then(|name| self.wayland_outputs.get(name))
+        {
+            return Some(output);
+        }
+
+        let mut internal = self
+            .wayland_outputs
+            .iter()
+            .filter(|(name, _)| is_internal_output(name))
+            .collect::<Vec<_>>();
+        internal.sort_by_key(|(name, _)| *name);
+        internal.first().map(|(_, (output, _))| output)
+    }
+
+    fn handle_overlap(&mut self) -> cosmic::app::Task<Msg> {
+        if self.indicators.is_empty() {
+            return Task::none();
+        }
         let Some((bl, br, tl, tr)) = self.size.as_ref().map(|s| {
             (
                 Rectangle::new(
@@ -275,10 +1097,10 @@ impl App {
                 ),
             )
         }) else {
-            return;
+            return Task::none();
         };
 
-        let (mut top, mut left, mut bottom, mut right) = (0, 0, 48, 0);
+        let (mut top, mut right, mut bottom, mut left) = self.config.margins();
         for overlap in self.overlap.values() {
             let tl = tl.intersects(overlap);
             let tr = tr.intersects(overlap);
@@ -301,7 +1123,13 @@ impl App {
                 continue;
             }
         }
-        state.margin = (top, right, bottom, left);
+        let tasks = self.indicators.iter_mut().map(|(id, state)| {
+            let id = *id;
+            state
+                .set_margin((top, right, bottom, left))
+                .map(move |x| cosmic::Action::App(Msg::OsdIndicator((id, x))))
+        });
+        iced::Task::batch(tasks.collect::<Vec<_>>())
     }
 
     fn trigger_identify_displays(&self) -> cosmic::app::Task<Msg> {
@@ -365,9 +1193,12 @@ impl cosmic::Application for App {
                 connection: None,
                 system_connection: None,
                 surfaces: HashMap::new(),
-                indicator: None,
+                indicators: HashMap::new(),
+                settings_daemon_sender: None,
                 display_brightness: None,
                 max_display_brightness: None,
+                keyboard_backlight_sender: None,
+                polkit_languages_sender: None,
                 keyboard_brightness: None,
                 max_keyboard_brightness: None,
                 audio_client: None,
@@ -377,9 +1208,12 @@ impl cosmic::Application for App {
                 overlap: HashMap::new(),
                 size: None,
                 action_to_confirm: None,
+                power_menu: None,
                 wayland_outputs: HashMap::new(),
+                pointer_output: None,
                 display_identifier_displays: HashMap::new(),
                 identifiers_dismissed: false,
+                config: config::osd_config(),
             },
             Task::none(),
         )
@@ -403,62 +1237,199 @@ impl cosmic::Application for App {
                     self.trigger_identify_displays()
                 } else if matches!(action, OsdTask::DismissDisplayIdentifiers) {
                     Task::done(cosmic::Action::App(Msg::DismissDisplayIdentifiers))
-                } else if matches!(action, OsdTask::Restart)
-                    && matches!(self.action_to_confirm, Some((_, OsdTask::Shutdown, _)))
+                } else if matches!(action, OsdTask::Restart { .. })
+                    && let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && matches!(confirmation.action, OsdTask::Shutdown { .. })
                 {
-                    action.perform()
+                    // Restarting from the shutdown dialog needs no further confirmation
+                    confirmation.action = action;
+                    self.perform_confirmed()
                 } else {
-                    let id = SurfaceId::unique();
-                    self.action_to_confirm = Some((id, action, COUNTDOWN_LENGTH));
-                    get_layer_surface(SctkLayerSurfaceSettings {
-                        id,
-                        keyboard_interactivity: KeyboardInteractivity::Exclusive,
-                        anchor: Anchor::empty(),
-                        namespace: "dialog".into(),
-                        size: None,
-                        size_limits: Limits::NONE.min_width(1.0).min_height(1.0),
-                        ..Default::default()
-                    })
+                    self.confirm_action(action)
                 }
             }
-            Msg::Confirm => {
-                if let Some((id, a, _)) = self.action_to_confirm.take() {
-                    Task::batch(vec![destroy_layer_surface(id), a.perform()])
-                } else {
-                    Task::none()
+            Msg::Confirm => self.perform_confirmed(),
+            Msg::Performed(id, result) => {
+                let Some(confirmation) = self
+                    .action_to_confirm
+                    .as_mut()
+                    .filter(|confirmation| confirmation.id == id)
+                else {
+                    if let Err(err) = result {
+                        log::error!("D-Bus error: {}", err);
+                    }
+                    return Task::none();
+                };
+                match result {
+                    Ok(()) => {
+                        self.action_to_confirm = None;
+                        destroy_layer_surface(id)
+                    }
+                    Err(err) => {
+                        log::error!("Failed to perform {:?}: {}", confirmation.action, err);
+                        confirmation.performing = false;
+                        confirmation.error = Some(err.to_string());
+                        Task::none()
+                    }
                 }
             }
+            Msg::Confirmation(action, capabilities) => self.show_confirmation(action, capabilities),
             Msg::Cancel => {
-                if let Some((id, _, _)) = self.action_to_confirm.take() {
-                    return destroy_layer_surface(id);
+                if let Some(confirmation) = self.action_to_confirm.take() {
+                    return destroy_layer_surface(confirmation.id);
+                }
+                if let Some(menu) = self.power_menu.take() {
+                    return destroy_layer_surface(menu.id);
                 }
                 Task::none()
             }
+            Msg::PowerMenu(capabilities) => self.show_power_menu(capabilities),
+            Msg::PowerMenuSelect(action) => {
+                let Some(menu) = self.power_menu.take() else {
+                    return Task::none();
+                };
+                Task::batch([destroy_layer_surface(menu.id), self.confirm_action(action)])
+            }
             Msg::Countdown => {
-                if let Some((surface_id, a, countdown)) = self.action_to_confirm.as_mut() {
-                    *countdown -= 1;
-                    if *countdown == 0 {
-                        let id = *surface_id;
-                        let a = a.clone();
-
-                        self.action_to_confirm = None;
-                        return Task::batch(vec![destroy_layer_surface(id), a.perform()]);
+                if let Some(confirmation) = self.action_to_confirm.as_mut() {
+                    let id = confirmation.id;
+                    // A countdown of 0 waits for the user instead, as does an action
+                    // that can't run or already failed
+                    if !confirmation.blocked()
+                        && confirmation.policy.countdown > 0
+                        && confirmation.capability() != Capability::No
+                        && !confirmation.performing
+                        && confirmation.error.is_none()
+                    {
+                        confirmation.countdown = confirmation.countdown.saturating_sub(1);
+                        if confirmation.countdown == 0 {
+                            return match confirmation.policy.on_timeout {
+                                OnTimeout::Perform => self.perform_confirmed(),
+                                OnTimeout::Cancel => {
+                                    self.action_to_confirm = None;
+                                    destroy_layer_surface(id)
+                                }
+                            };
+                        }
                     }
                 }
                 Task::none()
             }
+            Msg::InhibitorsChanged => {
+                let Some(confirmation) = self.action_to_confirm.as_ref() else {
+                    return Task::none();
+                };
+                let id = confirmation.id;
+                Task::batch([
+                    self.query_inhibitors(id, confirmation.action.inhibit_kind()),
+                    // Inhibitors decide whether the `*-ignore-inhibit` authorization
+                    // applies, which changes logind's answers
+                    cosmic::task::future(Capabilities::query()).map(move |capabilities| {
+                        cosmic::action::app(Msg::Capabilities(id, capabilities))
+                    }),
+                ])
+            }
+            Msg::Capabilities(id, capabilities) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && confirmation.id == id
+                {
+                    confirmation.capabilities = capabilities;
+                }
+                Task::none()
+            }
+            Msg::BootLoaderEntries(id, entries) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && confirmation.id == id
+                    && !entries.is_empty()
+                {
+                    confirmation.boot_entry_names = [fl!("default-boot-entry"), fl!("boot-menu")]
+                        .into_iter()
+                        .chain(entries.iter().map(|entry| boot_entry_name(entry)))
+                        .collect();
+                    confirmation.boot_entries = entries;
+                }
+                Task::none()
+            }
+            Msg::BootEntry(index) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut() {
+                    confirmation.action = match index {
+                        0 => OsdTask::Restart {
+                            schedule: Schedule::default(),
+                            install_updates: false,
+                        },
+                        1 => OsdTask::RebootTo { entry: None },
+                        _ => OsdTask::RebootTo {
+                            entry: confirmation.boot_entries.get(index - 2).cloned(),
+                        },
+                    };
+                }
+                Task::none()
+            }
+            Msg::OtherSessions(id, sessions) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && confirmation.id == id
+                {
+                    confirmation.other_sessions = sessions;
+                }
+                Task::none()
+            }
+            Msg::Updates(id, updates) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && confirmation.id == id
+                {
+                    confirmation.updates = updates;
+                }
+                Task::none()
+            }
+            Msg::InstallUpdates(power_off) => {
+                let Some(confirmation) = self.action_to_confirm.as_mut() else {
+                    return Task::none();
+                };
+                // Picked from the dialog, so it needs no further confirmation
+                confirmation.action = if power_off {
+                    OsdTask::Shutdown {
+                        schedule: Schedule::default(),
+                        install_updates: true,
+                    }
+                } else {
+                    OsdTask::Restart {
+                        schedule: Schedule::default(),
+                        install_updates: true,
+                    }
+                };
+                self.perform_confirmed()
+            }
+            Msg::Inhibitors(id, inhibitors) => {
+                if let Some(confirmation) = self.action_to_confirm.as_mut()
+                    && confirmation.id == id
+                {
+                    confirmation.inhibitors = inhibitors;
+                }
+                Task::none()
+            }
             Msg::DBus(event) => {
                 match event {
                     dbus::Event::Connection(connection) => self.connection = Some(connection),
                     dbus::Event::SystemConnection(connection) => {
                         self.system_connection = Some(connection)
                     }
+                    dbus::Event::Indicator(params) => return self.create_indicator(params),
                     dbus::Event::Error(context, err) => {
                         log::error!("Failed to {}: {}", context, err);
                     }
                 }
                 iced::Task::none()
             }
+            Msg::Language(requested) => {
+                if let Err(err) = crate::select_languages(&requested) {
+                    log::error!("Failed to load languages {:?}: {}", requested, err);
+                    return Task::none();
+                }
+                if let Some(sender) = &self.polkit_languages_sender {
+                    _ = sender.send(requested);
+                }
+                Task::none()
+            }
             Msg::PolkitAgent(event) => match event {
                 polkit_agent::Event::CreateDialog(params) => {
                     log::trace!("create polkit dialog: {}", params.cookie);
@@ -467,6 +1438,10 @@ impl cosmic::Application for App {
                     self.surfaces.insert(id, Surface::PolkitDialog(state));
                     cmd
                 }
+                polkit_agent::Event::Languages(sender) => {
+                    self.polkit_languages_sender = Some(sender);
+                    Task::none()
+                }
                 polkit_agent::Event::CancelDialog { cookie } => {
                     log::trace!("cancel polkit dialog: {}", cookie);
                     if let Some((id, _)) = self.surfaces.iter().find(|(_id, surface)| {
@@ -497,6 +1472,50 @@ impl cosmic::Application for App {
                 }
                 Task::none()
             }
+            Msg::ScheduleFailed(action, err) => {
+                log::error!("Failed to schedule {:?}: {}", action, err);
+                // The dialog's confirm button tries scheduling again
+                let task = self.show_confirmation(action, Capabilities::default());
+                if let Some(confirmation) = self.action_to_confirm.as_mut() {
+                    confirmation.error = Some(err);
+                }
+                task
+            }
+            Msg::ShutdownScheduled(kind, at) => {
+                // logind keeps a single schedule, so a new one replaces the banner
+                let mut tasks = Vec::new();
+                // Scheduled from the dialog after an earlier failure
+                if let Some(confirmation) = self
+                    .action_to_confirm
+                    .take_if(|confirmation| confirmation.action.is_scheduled())
+                {
+                    tasks.push(destroy_layer_surface(confirmation.id));
+                }
+                self.surfaces.retain(|id, surface| {
+                    if matches!(surface, Surface::ScheduledShutdown(_)) {
+                        tasks.push(destroy_layer_surface(*id));
+                        false
+                    } else {
+                        true
+                    }
+                });
+                let id = SurfaceId::unique();
+                let (state, cmd) = scheduled_shutdown::State::new(id, kind, at, self.config.margin);
+                self.surfaces.insert(id, Surface::ScheduledShutdown(state));
+                tasks.push(cmd);
+                Task::batch(tasks)
+            }
+            Msg::ScheduledShutdown((id, msg)) => {
+                if let Some(Surface::ScheduledShutdown(state)) = self.surfaces.remove(&id) {
+                    let (state, cmd) = state.update(msg);
+                    if let Some(state) = state {
+                        self.surfaces.insert(id, Surface::ScheduledShutdown(state));
+                    }
+                    return cmd
+                        .map(move |msg| cosmic::action::app(Msg::ScheduledShutdown((id, msg))));
+                }
+                Task::none()
+            }
             Msg::DisplayIdentifierSurface((id, msg)) => {
                 if let Some(Surface::OsdIndicator(state)) = self.surfaces.remove(&id) {
                     let (state, cmd) = state.update(msg);
@@ -512,18 +1531,41 @@ impl cosmic::Application for App {
                 }
                 Task::none()
             }
-            Msg::OsdIndicator(msg) => {
-                if let Some((id, state)) = self.indicator.take() {
-                    let (state, cmd) = state.update(msg);
-                    if let Some(state) = state {
-                        self.indicator = Some((id, state));
-                    }
-                    cmd.map(|x| cosmic::action::app(Msg::OsdIndicator(x)))
+            Msg::OsdIndicator((id, msg)) => {
+                let mut tasks = Vec::new();
+                if let osd_indicator::Msg::SetValue(value) = msg
+                    && let Some(state) = self.indicators.get(&id)
+                {
+                    tasks.push(self.set_indicator_value(state.params(), value));
+                }
+
+                // Indicators mirrored across outputs are dismissed and held together
+                let ids = if matches!(
+                    msg,
+                    osd_indicator::Msg::Dismiss | osd_indicator::Msg::Hover(_)
+                ) {
+                    self.indicators.keys().copied().collect()
                 } else {
-                    Task::none()
+                    vec![id]
+                };
+
+                for id in ids {
+                    if let Some(state) = self.indicators.remove(&id) {
+                        let (state, cmd) = state.update(msg.clone());
+                        if let Some(state) = state {
+                            self.indicators.insert(id, state);
+                        }
+                        tasks.push(
+                            cmd.map(move |x| cosmic::action::app(Msg::OsdIndicator((id, x)))),
+                        );
+                    }
                 }
+                Task::batch(tasks)
+            }
+            Msg::SettingsDaemon(settings_daemon::Event::Sender(sender)) => {
+                self.settings_daemon_sender = Some(sender);
+                Task::none()
             }
-            Msg::SettingsDaemon(settings_daemon::Event::Sender(_)) => Task::none(),
             Msg::SettingsDaemon(settings_daemon::Event::MaxDisplayBrightness(max_brightness)) => {
                 self.max_display_brightness = Some(max_brightness);
                 Task::none()
@@ -557,7 +1599,7 @@ impl cosmic::Application for App {
             }
             Msg::AudioClient(super::audio::Message::Client(client)) => {
                 if let Some(client) = Arc::into_inner(client) {
-                    self.audio_client = Some(Rc::new(RefCell::new(client)));
+                    self.audio_client = Some(Arc::new(tokio::sync::Mutex::new(client)));
                     self.audio = super::audio::Model::default();
                 }
                 Task::none()
@@ -565,20 +1607,34 @@ impl cosmic::Application for App {
             Msg::AudioClient(super::audio::Message::Subscription(message)) => {
                 match self.audio.update(message) {
                     None => Task::none(),
-                    Some(super::audio::Response::SinkVolume(volume, mute)) => {
+                    Some(super::audio::Response::SinkVolume(volume, mute, device)) => {
                         let now = Instant::now();
                         if now.duration_since(self.sink_last_playback) > Duration::from_millis(125)
                         {
                             self.sink_last_playback = now;
                             pipewire::play_audio_volume_change();
                         }
-                        self.create_indicator(osd_indicator::Params::SinkVolume(volume, mute))
-                    }
-                    Some(super::audio::Response::SourceVolume(volume, mute)) => {
-                        self.create_indicator(osd_indicator::Params::SourceVolume(volume, mute))
+                        self.create_indicator(osd_indicator::Params::SinkVolume(
+                            volume, mute, device,
+                        ))
                     }
+                    Some(super::audio::Response::SourceVolume(volume, mute, device)) => self
+                        .create_indicator(osd_indicator::Params::SourceVolume(
+                            volume, mute, device,
+                        )),
                 }
             }
+            Msg::LockKey(event) => match event {
+                lock_keys::Event::CapsLock(enabled) if self.config.lock_keys.caps_lock => {
+                    self.create_indicator(osd_indicator::Params::CapsLock(enabled))
+                }
+                lock_keys::Event::NumLock(enabled) if self.config.lock_keys.num_lock => {
+                    self.create_indicator(osd_indicator::Params::NumLock(enabled))
+                }
+                _ => Task::none(),
+            },
+            Msg::Power(power) => self.create_indicator(osd_indicator::Params::Power(power)),
+            Msg::Media(media) => self.create_indicator(osd_indicator::Params::Media(media)),
             Msg::AirplaneMode(state) => {
                 if self.airplane_mode.is_none() {
                     self.airplane_mode = Some(state);
@@ -589,7 +1645,10 @@ impl cosmic::Application for App {
                 Task::none()
             }
             Msg::KeyboardBacklight(update) => match update {
-                KeyboardBacklightUpdate::Sender(_) => Task::none(),
+                KeyboardBacklightUpdate::Sender(sender) => {
+                    self.keyboard_backlight_sender = Some(sender);
+                    Task::none()
+                }
                 KeyboardBacklightUpdate::MaxBrightness(max_brightness) => {
                     self.max_keyboard_brightness = Some(max_brightness);
                     Task::none()
@@ -612,31 +1671,27 @@ impl cosmic::Application for App {
                     }
                 }
             },
-            Msg::Overlap(overlap_notify_event) => {
-                match overlap_notify_event {
-                    OverlapNotifyEvent::OverlapLayerAdd {
-                        identifier,
-                        namespace,
-                        logical_rect,
-                        exclusive,
-                        ..
-                    } if (namespace == "Dock" || namespace == "Panel" || exclusive > 0) => {
-                        self.overlap.insert(identifier, logical_rect);
-                        self.handle_overlap();
-                    }
-                    OverlapNotifyEvent::OverlapLayerRemove { identifier }
-                        if self.overlap.remove(&identifier).is_some() =>
-                    {
-                        self.handle_overlap();
-                    }
-                    _ => {}
+            Msg::Overlap(overlap_notify_event) => match overlap_notify_event {
+                OverlapNotifyEvent::OverlapLayerAdd {
+                    identifier,
+                    namespace,
+                    logical_rect,
+                    exclusive,
+                    ..
+                } if (namespace == "Dock" || namespace == "Panel" || exclusive > 0) => {
+                    self.overlap.insert(identifier, logical_rect);
+                    self.handle_overlap()
                 }
-                Task::none()
-            }
+                OverlapNotifyEvent::OverlapLayerRemove { identifier }
+                    if self.overlap.remove(&identifier).is_some() =>
+                {
+                    self.handle_overlap()
+                }
+                _ => Task::none(),
+            },
             Msg::Size(size) => {
                 self.size = Some(size);
-                self.handle_overlap();
-                Task::none()
+                self.handle_overlap()
             }
             Msg::Zbus(result) => {
                 if let Err(e) = result {
@@ -657,7 +1712,7 @@ impl cosmic::Application for App {
                 )
             }
             Msg::SoundSettings => {
-                if let Some(id) = self.action_to_confirm.as_ref().map(|a| a.0) {
+                if let Some(id) = self.action_to_confirm.as_ref().map(|c| c.id) {
                     request_token(Some(String::from(Self::APP_ID)), Some(id))
                         .map(move |token| cosmic::Action::App(Msg::ActivationToken(token)))
                 } else {
@@ -666,49 +1721,32 @@ impl cosmic::Application for App {
                 }
             }
             Msg::Headphones(value) => {
-                if let Some((
-                    _,
-                    OsdTask::ConfirmHeadphones {
-                        selected_headset, ..
-                    },
-                    _,
-                )) = self.action_to_confirm.as_mut()
+                if let Some(Confirmation {
+                    action:
+                        OsdTask::ConfirmHeadphones {
+                            selected_headset, ..
+                        },
+                    ..
+                }) = self.action_to_confirm.as_mut()
                 {
                     *selected_headset = value;
                 }
                 Task::none()
             }
             Msg::TouchpadEnabled(enabled) => {
-                let mut cmds = Vec::new();
                 let Some(enabled) = enabled else {
                     log::warn!("TouchpadEnabled event received with None value");
                     return Task::none();
                 };
                 // Show the OSD indicator for touchpad enabled/disabled
-                let id = SurfaceId::unique();
-                let (state, cmd) =
-                    osd_indicator::State::new(id, osd_indicator::Params::TouchpadEnabled(enabled));
-                if let Some(old) = self.indicator.replace((id, state)) {
-                    cmds.push(destroy_layer_surface(old.0));
-                }
-                cmds.push(cmd);
-                iced::Task::batch(cmds).map(|x| cosmic::Action::App(Msg::OsdIndicator(x)))
+                self.replace_indicators(osd_indicator::Params::TouchpadEnabled(enabled))
             }
             Msg::Display(enabled) => {
-                let mut cmds = Vec::new();
-
                 let Some(enabled) = enabled else {
                     log::warn!("Display event received with None value");
                     return Task::none();
                 };
-                let id = SurfaceId::unique();
-                let (state, cmd) =
-                    osd_indicator::State::new(id, osd_indicator::Params::DisplayToggle(enabled));
-                if let Some(old) = self.indicator.replace((id, state)) {
-                    cmds.push(destroy_layer_surface(old.0));
-                }
-                cmds.push(cmd);
-                iced::Task::batch(cmds).map(|x| cosmic::Action::App(Msg::OsdIndicator(x)))
+                self.replace_indicators(osd_indicator::Params::DisplayToggle(enabled))
             }
             Msg::OutputInfo(output, name) => {
                 let is_new = !self.wayland_outputs.contains_key(&name);
@@ -721,6 +1759,9 @@ impl cosmic::Application for App {
                 Task::none()
             }
             Msg::OutputRemoved(output) => {
+                if self.pointer_output.as_ref() == Some(&output) {
+                    self.pointer_output = None;
+                }
                 // Find and remove the output from our tracking map
                 let mut removed_name = None;
                 self.wayland_outputs.retain(|name, (out, _)| {
@@ -746,6 +1787,17 @@ impl cosmic::Application for App {
                     Task::none()
                 }
             }
+            Msg::PointerEntered(id) => {
+                let state = match self.surfaces.get(&id) {
+                    Some(Surface::OsdIndicator(state)) => Some(state),
+                    _ => self.indicators.get(&id),
+                };
+                // Surfaces on the active output don't say which one that is
+                if let Some(IcedOutput::Output(output)) = state.map(|state| state.output()) {
+                    self.pointer_output = Some(output.clone());
+                }
+                Task::none()
+            }
             Msg::CreateDisplayIdentifiers(displays) => {
                 if displays.is_empty() {
                     log::warn!("CreateDisplayIdentifiers called with empty display list");
@@ -837,6 +1889,7 @@ impl cosmic::Application for App {
                                 id,
                                 osd_indicator::Params::DisplayNumber(*display_number),
                                 iced_output,
+                                &self.config,
                             );
 
                             self.surfaces.insert(id, Surface::OsdIndicator(state));
@@ -868,6 +1921,7 @@ impl cosmic::Application for App {
                             id,
                             osd_indicator::Params::DisplayNumber(*display_number),
                             iced_output,
+                            &self.config,
                         );
 
                         self.surfaces.insert(id, Surface::OsdIndicator(state));
@@ -944,9 +1998,72 @@ impl cosmic::Application for App {
 
                 Task::batch(tasks)
             }
+            Msg::Config(config) => {
+                let output_changed = config.output != self.config.output
+                    || config.primary_output != self.config.primary_output;
+                self.config = config;
+
+                let mut tasks = Vec::new();
+                if output_changed && let Some(state) = self.indicators.values().next() {
+                    // Surfaces can't move between outputs, so recreate them
+                    let params = state.params().clone();
+                    tasks.push(self.replace_indicators(params));
+                }
+                tasks.push(self.handle_overlap());
+
+                for (id, state) in &mut self.indicators {
+                    let id = *id;
+                    tasks.push(
+                        state
+                            .set_config(&self.config)
+                            .map(move |x| cosmic::action::app(Msg::OsdIndicator((id, x)))),
+                    );
+                }
+                for (id, surface) in &mut self.surfaces {
+                    if let Surface::OsdIndicator(state) = surface {
+                        let id = *id;
+                        tasks.push(state.set_config(&self.config).map(move |msg| {
+                            cosmic::action::app(Msg::DisplayIdentifierSurface((id, msg)))
+                        }));
+                    }
+                }
+                Task::batch(tasks)
+            }
+            Msg::Unfocused(id) => {
+                if let Some(confirmation) = self.action_to_confirm.as_ref()
+                    && confirmation.id == id
+                {
+                    // Authenticating the action moves focus to the polkit dialog
+                    if confirmation.performing {
+                        return Task::none();
+                    }
+                    return Task::done(cosmic::Action::App(Msg::Cancel));
+                }
+                // Picking from the power menu closes it before the dialog opens, so
+                // only its own loss of focus cancels it
+                if self.power_menu.as_ref().is_some_and(|menu| menu.id == id) {
+                    return Task::done(cosmic::Action::App(Msg::Cancel));
+                }
+                Task::none()
+            }
+            // Arrow keys move between the power menu's choices, as Tab does
+            Msg::FocusNext => {
+                if self.power_menu.is_none() || self.action_to_confirm.is_some() {
+                    return Task::none();
+                }
+                focus_next::<()>().discard()
+            }
+            Msg::FocusPrevious => {
+                if self.power_menu.is_none() || self.action_to_confirm.is_some() {
+                    return Task::none();
+                }
+                focus_previous::<()>().discard()
+            }
             Msg::Focused => {
                 if self.action_to_confirm.is_some() {
                     focus::<()>(CANCEL_ID.clone()).discard()
+                } else if self.power_menu.is_some() {
+                    focus_next::<()>().discard()
                 } else if let Some(state) = self.surfaces.values().find_map(|surface| {
                     if let Surface::PolkitDialog(state) = surface {
                         Some(state)
@@ -968,11 +2085,28 @@ impl cosmic::Application for App {
         subscriptions.push(dbus::subscription().map(Msg::DBus));
 
         if let Some(connection) = self.system_connection.clone() {
-            subscriptions.push(polkit_agent::subscription(connection).map(Msg::PolkitAgent));
+            subscriptions
+                .push(polkit_agent::subscription(connection.clone()).map(Msg::PolkitAgent));
+            subscriptions.push(language::subscription(connection.clone()).map(Msg::Language));
+            subscriptions.push(upower::subscription(connection.clone()).map(Msg::Power));
+
+            // Apps can take or release inhibitors while the dialog is open
+            if self
+                .action_to_confirm
+                .as_ref()
+                .is_some_and(|confirmation| confirmation.action.inhibit_kind().is_some())
+            {
+                subscriptions.push(
+                    inhibitors::changes(connection, self.connection.clone())
+                        .map(|()| Msg::InhibitorsChanged),
+                );
+            }
         }
 
         if let Some(connection) = self.connection.clone() {
-            subscriptions.push(settings_daemon::subscription(connection).map(Msg::SettingsDaemon));
+            subscriptions
+                .push(settings_daemon::subscription(connection.clone()).map(Msg::SettingsDaemon));
+            subscriptions.push(mpris::subscription(connection).map(Msg::Media));
         }
 
         subscriptions.push(iced::Subscription::run(|| {
@@ -984,19 +2118,39 @@ impl cosmic::Application for App {
             )
         }));
 
+        subscriptions.push(
+            cosmic_config::config_subscription::<_, OsdConfig>(
+                TypeId::of::<OsdConfig>(),
+                config::OSD_CONFIG.into(),
+                OsdConfig::VERSION,
+            )
+            .map(|update| {
+                for err in update.errors {
+                    log::debug!("Failed to read {}: {}", config::OSD_CONFIG, err);
+                }
+                Msg::Config(update.config)
+            }),
+        );
+
         subscriptions.push(airplane_mode::subscription().map(Msg::AirplaneMode));
 
+        if self.config.lock_keys.any() {
+            subscriptions
+                .push(lock_keys::subscription(self.config.lock_keys.clone()).map(Msg::LockKey));
+        }
+
         subscriptions.push(kbd_backlight_subscription("kbd-backlight").map(Msg::KeyboardBacklight));
 
-        subscriptions.push(listen_with(|event, _, _id| match event {
+        subscriptions.push(listen_with(|event, _, id| match event {
             event::Event::Window(iced::window::Event::Opened { position: _, size }) => {
                 Some(Msg::Size(size))
             }
             event::Event::Window(iced::window::Event::Resized(s)) => Some(Msg::Size(s)),
+            event::Event::Mouse(iced::mouse::Event::CursorEntered) => Some(Msg::PointerEntered(id)),
             event::Event::PlatformSpecific(event::PlatformSpecific::Wayland(wayland_event)) => {
                 match wayland_event {
                     wayland::Event::OverlapNotify(event, ..) => Some(Msg::Overlap(event)),
-                    wayland::Event::Layer(LayerEvent::Unfocused, ..) => Some(Msg::Cancel),
+                    wayland::Event::Layer(LayerEvent::Unfocused, _, id) => Some(Msg::Unfocused(id)),
                     wayland::Event::Layer(LayerEvent::Focused, ..) => Some(Msg::Focused),
                     wayland::Event::Output(output_event, output) => {
                         match output_event {
@@ -1034,18 +2188,37 @@ impl cosmic::Application for App {
                 modifiers: _,
                 ..
             }) => Some(Msg::Cancel),
+            cosmic::iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::ArrowDown),
+                ..
+            }) => Some(Msg::FocusNext),
+            cosmic::iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::ArrowUp),
+                ..
+            }) => Some(Msg::FocusPrevious),
             _ => None,
         }));
 
+        subscriptions.extend(self.surfaces.iter().filter_map(|(id, surface)| {
+            match surface {
+                Surface::PolkitDialog(state) => {
+                    Some(state.subscription().with(*id).map(Msg::PolkitDialog))
+                }
+                Surface::OsdIndicator(state) => Some(
+                    state
+                        .subscription()
+                        .with(*id)
+                        .map(Msg::DisplayIdentifierSurface),
+                ),
+                Surface::ScheduledShutdown(state) => {
+                    Some(state.subscription().with(*id).map(Msg::ScheduledShutdown))
+                }
+            }
+        }));
         subscriptions.extend(
-            self.surfaces
+            self.indicators
                 .iter()
-                .filter_map(|(id, surface)| match surface {
-                    Surface::PolkitDialog(state) => {
-                        Some(state.subscription().with(*id).map(Msg::PolkitDialog))
-                    }
-                    Surface::OsdIndicator(_) => None, // OSD indicators don't have subscriptions
-                }),
+                .map(|(id, state)| state.subscription().with(*id).map(Msg::OsdIndicator)),
         );
         if self.action_to_confirm.is_some() {
             subscriptions.push(time::every(Duration::from_millis(1000)).map(|_| Msg::Countdown));
@@ -1067,24 +2240,37 @@ impl cosmic::Application for App {
                 Surface::OsdIndicator(state) => state
                     .view()
                     .map(move |msg| Msg::DisplayIdentifierSurface((id, msg))),
+                Surface::ScheduledShutdown(state) => state
+                    .view()
+                    .map(move |msg| Msg::ScheduledShutdown((id, msg))),
             };
-        } else if let Some((indicator_id, state)) = &self.indicator {
-            if id == *indicator_id {
-                return state.view().map(Msg::OsdIndicator);
-            }
-        } else if matches!(self.action_to_confirm, Some((c_id, _, _)) if c_id == id) {
+        } else if let Some(state) = self.indicators.get(&id) {
+            return state.view().map(move |msg| Msg::OsdIndicator((id, msg)));
+        } else if let Some(menu) = self.power_menu.as_ref().filter(|menu| menu.id == id) {
+            return self.view_power_menu(menu);
+        } else if let Some(confirmation) = self
+            .action_to_confirm
+            .as_ref()
+            .filter(|confirmation| confirmation.id == id)
+        {
             let cosmic_theme = self.core.system_theme().cosmic();
-            let (_, cur_action, countdown) = self.action_to_confirm.as_ref().unwrap();
+            let cur_action = &confirmation.action;
+            let countdown = confirmation.countdown;
             let action = match *cur_action {
                 OsdTask::EnterBios => "enter-bios",
                 OsdTask::LogOut => "log-out",
-                OsdTask::Restart => "restart",
-                OsdTask::Shutdown => "shutdown",
+                OsdTask::Restart { .. } | OsdTask::RebootTo { .. } => "restart",
+                OsdTask::Shutdown { .. } => "shutdown",
+                OsdTask::Suspend => "suspend",
+                OsdTask::Hibernate => "hibernate",
+                OsdTask::HybridSleep => "hybrid-sleep",
+                OsdTask::LockScreen => "lock-screen",
                 OsdTask::ConfirmHeadphones { .. } => "confirm-device-type",
                 OsdTask::Touchpad => "touchpad",
                 OsdTask::Display => "external-display",
                 OsdTask::IdentifyDisplays => "identify-displays",
                 OsdTask::DismissDisplayIdentifiers => "dismiss-display-identifiers",
+                OsdTask::PowerMenu => "power-menu",
             };
 
             let title = fl!(
@@ -1092,20 +2278,139 @@ impl cosmic::Application for App {
                 HashMap::from_iter(vec![("action", action)])
             );
             let countdown = &countdown.to_string();
+            let blocked = confirmation.blocked();
             let mut dialog = widget::dialog().title(title);
+            let space_xs = self.core.system_theme().cosmic().space_xs();
+            let space_s = self.core.system_theme().cosmic().space_s();
+
+            // Shown below the body, or below the inhibitors while blocked
+            let mut controls: Vec<Element<'_, Msg>> = Vec::new();
+            if !confirmation.other_sessions.is_empty() {
+                let mut users = confirmation
+                    .other_sessions
+                    .iter()
+                    .map(|session| session.user.as_str())
+                    .collect::<Vec<_>>();
+                users.sort_unstable();
+                users.dedup();
+                controls.push(
+                    widget::column::with_children(
+                        std::iter::once(text::body(fl!("other-users", count = users.len())).into())
+                            .chain(confirmation.other_sessions.iter().map(|session| {
+                                let location = if !session.remote {
+                                    fl!("session-local", location = session.location.as_str())
+                                } else if session.location.is_empty() {
+                                    fl!("session-remote")
+                                } else {
+                                    fl!("session-remote-from", host = session.location.as_str())
+                                };
+                                widget::row::with_children([
+                                    icon::from_name("avatar-default-symbolic").size(32).into(),
+                                    widget::column::with_children([
+                                        text::body(&session.user).into(),
+                                        text::caption(location).into(),
+                                    ])
+                                    .into(),
+                                ])
+                                .spacing(space_s)
+                                .align_y(Alignment::Center)
+                                .into()
+                            })),
+                    )
+                    .spacing(space_xs)
+                    .into(),
+                );
+            }
+            let boot_entry_picker = confirmation
+                .boot_entry()
+                .filter(|_| {
+                    !confirmation.boot_entry_names.is_empty()
+                        && confirmation.capabilities.reboot_to_boot_loader_entry != Capability::No
+                })
+                .map(|selected| {
+                    widget::column::with_children([
+                        text::body(fl!("boot-into")).into(),
+                        widget::dropdown(
+                            &confirmation.boot_entry_names,
+                            Some(selected),
+                            Msg::BootEntry,
+                        )
+                        .into(),
+                    ])
+                    .spacing(self.core.system_theme().cosmic().space_xxs())
+                });
+            controls.extend(boot_entry_picker.map(Element::from));
+            if confirmation.updates.any()
+                && matches!(
+                    cur_action,
+                    OsdTask::Restart { .. } | OsdTask::Shutdown { .. }
+                )
+            {
+                let install_button = |label: String, power_off: bool| {
+                    let action = if power_off {
+                        OsdTask::Shutdown {
+                            schedule: Schedule::default(),
+                            install_updates: true,
+                        }
+                    } else {
+                        OsdTask::Restart {
+                            schedule: Schedule::default(),
+                            install_updates: true,
+                        }
+                    };
+                    let capability = confirmation.capabilities.of(&action);
+                    let mut button = button::standard(label).on_press_maybe(
+                        (capability != Capability::No && !confirmation.performing)
+                            .then_some(Msg::InstallUpdates(power_off)),
+                    );
+                    if capability == Capability::Challenge {
+                        button = button.leading_icon(icon::from_name("dialog-password-symbolic"));
+                    }
+                    Element::from(button)
+                };
+                let mut children = Vec::new();
+                // Only PackageKit's update needs installing. Staged firmware is
+                // installed by the next boot whichever button is pressed.
+                if confirmation.updates.offline {
+                    let mut buttons = Vec::new();
+                    if matches!(cur_action, OsdTask::Shutdown { .. }) {
+                        buttons.push(install_button(fl!("install-updates-shutdown"), true));
+                    }
+                    buttons.push(install_button(fl!("install-updates-restart"), false));
+                    children.push(text::body(fl!("updates-ready")).into());
+                    children.push(widget::row::with_children(buttons).spacing(space_xs).into());
+                }
+                if confirmation.updates.firmware {
+                    children.push(text::body(fl!("firmware-updates-ready")).into());
+                }
+                controls.push(
+                    widget::column::with_children(children)
+                        .spacing(self.core.system_theme().cosmic().space_xxs())
+                        .into(),
+                );
+            }
 
             dialog = dialog
                 .primary_action(
                     button::custom(min_width_and_height(
-                        text::body(fl!("confirm", HashMap::from_iter(vec![("action", action)])))
-                            .into(),
+                        authentication_label(
+                            if blocked {
+                                fl!("proceed-anyway")
+                            } else {
+                                fl!("confirm", HashMap::from_iter(vec![("action", action)]))
+                            },
+                            confirmation.capability(),
+                        ),
                         142.0,
                         32.0,
                     ))
                     .padding([0, cosmic_theme.space_s()])
                     .id(CONFIRM_ID.clone())
                     .class(theme::Button::Suggested)
-                    .on_press(Msg::Confirm),
+                    .on_press_maybe(
+                        (confirmation.capability() != Capability::No && !confirmation.performing)
+                            .then_some(Msg::Confirm),
+                    ),
                 )
                 .secondary_action(
                     button::custom(min_width_and_height(
@@ -1138,11 +2443,11 @@ impl cosmic::Application for App {
                                 )
                                 .class(cosmic::theme::style::Button::Image)
                                 .selected(matches!(
-                                    self.action_to_confirm,
-                                    Some((_, OsdTask::ConfirmHeadphones {
+                                    cur_action,
+                                    OsdTask::ConfirmHeadphones {
                                         selected_headset,
                                         ..
-                                    },_)) if !selected_headset
+                                    } if !selected_headset
                                 ))
                                 .on_press(Msg::Headphones(false))
                                 .into(),
@@ -1161,11 +2466,11 @@ impl cosmic::Application for App {
                                 )
                                 .class(cosmic::theme::style::Button::Image)
                                 .selected(matches!(
-                                    self.action_to_confirm,
-                                    Some((_, OsdTask::ConfirmHeadphones {
+                                    cur_action,
+                                    OsdTask::ConfirmHeadphones {
                                         selected_headset,
                                         ..
-                                    },_)) if selected_headset
+                                    } if selected_headset
                                 ))
                                 .on_press(Msg::Headphones(true))
                                 .into(),
@@ -1179,27 +2484,123 @@ impl cosmic::Application for App {
                         .apply(container)
                         .center_x(Length::Fixed(522.)),
                     )
-            } else {
+            } else if let Some(error) = &confirmation.error {
                 dialog
-                    .icon(text_icon(
-                        match cur_action {
-                            OsdTask::LogOut => "system-log-out-symbolic",
-                            OsdTask::Restart | OsdTask::EnterBios => "system-restart-symbolic",
-                            OsdTask::Shutdown => "system-shutdown-symbolic",
-                            _ => unreachable!(),
-                        },
-                        60,
+                    .icon(text_icon("dialog-error-symbolic", 60))
+                    .body(fl!(
+                        "confirm-failed",
+                        HashMap::from_iter(vec![("action", action), ("error", error.as_str())])
                     ))
+            } else if confirmation.capability() == Capability::No {
+                dialog
+                    .icon(text_icon("dialog-warning-symbolic", 60))
                     .body(fl!(
+                        "confirm-unavailable",
+                        HashMap::from_iter(vec![("action", action)])
+                    ))
+            } else if blocked {
+                dialog
+                    .icon(text_icon("dialog-warning-symbolic", 60))
+                    .body(fl!(
+                        "confirm-blocked",
+                        HashMap::from_iter(vec![
+                            ("action", action),
+                            // What happens once the inhibitors are released
+                            (
+                                "then",
+                                if confirmation.policy.countdown == 0 {
+                                    "wait"
+                                } else if confirmation.policy.on_timeout == OnTimeout::Cancel {
+                                    "cancel"
+                                } else {
+                                    "perform"
+                                }
+                            )
+                        ])
+                    ))
+                    .control(
+                        widget::column::with_children(
+                            confirmation
+                                .inhibitors
+                                .iter()
+                                .map(|inhibitor| inhibitor_row(inhibitor, t.space_s()))
+                                // Proceeding anyway still ends other sessions, and can
+                                // boot into another entry
+                                .chain(controls),
+                        )
+                        .spacing(t.space_xs()),
+                    )
+            } else {
+                let dialog = dialog.icon(text_icon(
+                    match cur_action {
+                        OsdTask::LogOut => "system-log-out-symbolic",
+                        OsdTask::Restart { .. } | OsdTask::EnterBios | OsdTask::RebootTo { .. } => {
+                            "system-restart-symbolic"
+                        }
+                        OsdTask::Shutdown { .. } => "system-shutdown-symbolic",
+                        OsdTask::Suspend | OsdTask::Hibernate | OsdTask::HybridSleep => {
+                            "system-suspend-symbolic"
+                        }
+                        OsdTask::LockScreen => "system-lock-screen-symbolic",
+                        _ => unreachable!(),
+                    },
+                    60,
+                ));
+                // Weak inhibitors are listed, but don't hold the countdown back
+                controls.splice(
+                    0..0,
+                    confirmation
+                        .inhibitors
+                        .iter()
+                        .map(|inhibitor| inhibitor_row(inhibitor, space_s)),
+                );
+                let dialog = if controls.is_empty() {
+                    dialog
+                } else {
+                    dialog.control(widget::column::with_children(controls).spacing(space_s))
+                };
+                let body = match confirmation.policy {
+                    ActionPolicy { countdown: 0, .. } => None,
+                    ActionPolicy {
+                        on_timeout: OnTimeout::Perform,
+                        ..
+                    } => Some(fl!(
                         "confirm-body",
                         HashMap::from_iter(vec![("action", action), ("countdown", countdown)])
-                    ))
+                    )),
+                    ActionPolicy {
+                        on_timeout: OnTimeout::Cancel,
+                        ..
+                    } => Some(fl!(
+                        "confirm-body-cancel",
+                        HashMap::from_iter(vec![("countdown", countdown)])
+                    )),
+                };
+                let body = if confirmation.capability() == Capability::Challenge {
+                    let note = fl!("requires-authentication");
+                    Some(body.map_or(note.clone(), |body| format!("{body} {note}")))
+                } else {
+                    body
+                };
+                match body {
+                    Some(body) => dialog.body(body),
+                    None => dialog,
+                }
             };
 
-            if matches!(cur_action, OsdTask::Shutdown) {
-                dialog = dialog.tertiary_action(
-                    button::text(fl!("restart")).on_press(Msg::Action(OsdTask::Restart)),
+            if matches!(cur_action, OsdTask::Shutdown { .. }) {
+                let mut restart = button::text(fl!("restart")).on_press_maybe(
+                    (confirmation.capabilities.reboot != Capability::No
+                        && !confirmation.performing)
+                        .then_some(Msg::Action(OsdTask::Restart {
+                            schedule: Schedule::default(),
+                            install_updates: false,
+                        })),
                 );
+                if confirmation.capabilities.reboot == Capability::Challenge {
+                    restart = restart.leading_icon(icon::from_name("dialog-password-symbolic"));
+                }
+                dialog = dialog.tertiary_action(restart);
             }
 
             return Element::from(
@@ -1277,21 +2678,15 @@ impl cosmic::Application for App {
                             .collect::<Vec<_>>();
                         enabled_positions.sort_by_key(|p| p.0.0);
 
-                        let other_enabled = output_lists.outputs.values().any(|o| {
-                            !(o.name.starts_with("eDP-")
-                                || o.name.starts_with("LVDS-")
-                                || o.name.starts_with("DSI-"))
-                                && o.enabled
-                        });
+                        let other_enabled = output_lists
+                            .outputs
+                            .values()
+                            .any(|o| !is_internal_output(&o.name) && o.enabled);
 
                         let mut internal = output_lists
                             .outputs
                             .values_mut()
-                            .filter(|o| {
-                                o.name.starts_with("eDP-")
-                                    || o.name.starts_with("LVDS-")
-                                    || o.name.starts_with("DSI-")
-                            })
+                            .filter(|o| is_internal_output(&o.name))
                             .collect::<Vec<_>>();
                         if internal.is_empty() {
                             log::error!("No internal display found");
@@ -1388,21 +2783,7 @@ impl cosmic::Application for App {
                     return Task::done(cosmic::Action::App(Msg::DismissDisplayIdentifiers));
                 }
 
-                if let Some(prev) = self.action_to_confirm.take() {
-                    self.action_to_confirm = Some((prev.0, cmd, COUNTDOWN_LENGTH));
-                } else {
-                    let id = SurfaceId::unique();
-                    self.action_to_confirm = Some((id, cmd, COUNTDOWN_LENGTH));
-                    return get_layer_surface(SctkLayerSurfaceSettings {
-                        id,
-                        keyboard_interactivity: KeyboardInteractivity::Exclusive,
-                        anchor: Anchor::empty(),
-                        namespace: "dialog".into(),
-                        size: None,
-                        size_limits: Limits::NONE.min_width(1.0).min_height(1.0),
-                        ..Default::default()
-                    });
-                }
+                return self.confirm_action(cmd);
             }
             Details::Open { .. } => {}
         }
@@ -1464,6 +2845,49 @@ fn min_width_and_height(
     .align_x(Alignment::Center)
 }
 
+/// Button label, with a padlock when logind will ask for authentication
+fn authentication_label(label: String, capability: Capability) -> Element<'static, Msg> {
+    if capability == Capability::Challenge {
+        widget::row::with_children([
+            text_icon("dialog-password-symbolic", 16).into(),
+            text::body(label).into(),
+        ])
+        .spacing(theme::active().cosmic().space_xxs())
+        .align_y(Alignment::Center)
+        .into()
+    } else {
+        text::body(label).into()
+    }
+}
+
 fn text_icon(name: &str, size: u16) -> widget::Icon {
     icon::from_name(name).size(size).symbolic(true).icon()
 }
+
+/// An app holding an inhibitor, with its reason
+fn inhibitor_row(inhibitor: &Inhibitor, spacing: u16) -> Element<'_, Msg> {
+    let mut details = widget::column::with_children([
+        text::body(&inhibitor.app).into(),
+        text::caption(&inhibitor.reason).into(),
+    ]);
+    if inhibitor.weak {
+        details = details.push(text::caption(fl!("inhibitor-weak")));
+    }
+    widget::row::with_children([
+        icon::from_name(inhibitor.icon_name.as_str())
+            .size(32)
+            .fallback(Some(icon::IconFallback::Names(vec![
+                "application-x-executable-symbolic".into(),
+            ])))
+            .into(),
+        details.into(),
+    ])
+    .spacing(spacing)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+/// Whether the connector name belongs to a built-in laptop panel.
+fn is_internal_output(name: &str) -> bool {
+    name.starts_with("eDP-") || name.starts_with("LVDS-") || name.starts_with("DSI-")
+}
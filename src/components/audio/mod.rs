@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub mod model;
-pub use model::{Model, Response};
+pub use model::{Device, Model, Response};
 use std::sync::Arc;
 
 pub use cosmic_settings_audio_client as audio_client;
@@ -21,6 +21,7 @@ pub struct Nodes {
     mute: Vec<bool>,
     id: Vec<NodeId>,
     volume: Vec<u32>,
+    device: Vec<Device>,
 }
 
 impl Nodes {
@@ -31,6 +32,7 @@ impl Nodes {
         self.mute.remove(pos);
         self.id.remove(pos);
         self.volume.remove(pos);
+        self.device.remove(pos);
         if self.active == Some(pos) {
             self.active = None;
         }
@@ -42,14 +44,30 @@ impl Nodes {
 pub struct ActiveNode {
     pub volume: u32,
     pub mute: bool,
+    pub device: Device,
+}
+
+/// What a node is called and looks like in the volume indicator
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Device {
+    pub description: String,
+    pub icon_name: String,
 }
 
 pub enum Response {
-    SinkVolume(u32, bool),
-    SourceVolume(u32, bool),
+    SinkVolume(u32, bool, Device),
+    SourceVolume(u32, bool, Device),
 }
 
 impl Model {
+    pub fn default_sink(&self) -> Option<NodeId> {
+        self.default_sink
+    }
+
+    pub fn default_source(&self) -> Option<NodeId> {
+        self.default_source
+    }
+
     pub fn update(&mut self, event: audio_client::Event) -> Option<Response> {
         match event {
             audio_client::Event::NodeMute(node_id, mute) => {
@@ -57,13 +75,21 @@ impl Model {
                     self.sinks.mute[pos] = mute;
                     if self.sinks.active == Some(pos) && self.active_sink.mute != mute {
                         self.active_sink.mute = mute;
-                        return Some(Response::SinkVolume(self.sinks.volume[pos], mute));
+                        return Some(Response::SinkVolume(
+                            self.sinks.volume[pos],
+                            mute,
+                            self.sinks.device[pos].clone(),
+                        ));
                     }
                 } else if let Some(pos) = self.sources.id.iter().position(|id| node_id == *id) {
                     self.sources.mute[pos] = mute;
                     if self.sources.active == Some(pos) && self.active_source.mute != mute {
                         self.active_source.mute = mute;
-                        return Some(Response::SourceVolume(self.sources.volume[pos], mute));
+                        return Some(Response::SourceVolume(
+                            self.sources.volume[pos],
+                            mute,
+                            self.sources.device[pos].clone(),
+                        ));
                     }
                 }
             }
@@ -82,6 +108,7 @@ impl Model {
                         return changed.then_some(Response::SinkVolume(
                             self.active_sink.volume,
                             self.active_sink.mute,
+                            self.active_sink.device.clone(),
                         ));
                     }
                 } else if let Some(pos) = self.sources.id.iter().position(|id| node_id == *id) {
@@ -99,6 +126,7 @@ impl Model {
                         return changed.then_some(Response::SourceVolume(
                             self.active_source.volume,
                             self.active_source.mute,
+                            self.active_source.device.clone(),
                         ));
                     }
                 }
@@ -110,9 +138,11 @@ impl Model {
                     self.sinks.active = Some(pos);
                     self.active_sink.mute = self.sinks.mute[pos];
                     self.active_sink.volume = self.sinks.volume[pos];
+                    self.active_sink.device = self.sinks.device[pos].clone();
                     return Some(Response::SinkVolume(
                         self.active_sink.volume,
                         self.active_sink.mute,
+                        self.active_sink.device.clone(),
                     ));
                 }
             }
@@ -123,14 +153,20 @@ impl Model {
                     self.sources.active = Some(pos);
                     self.active_source.mute = self.sources.mute[pos];
                     self.active_source.volume = self.sources.volume[pos];
+                    self.active_source.device = self.sources.device[pos].clone();
                     return Some(Response::SourceVolume(
                         self.active_source.volume,
                         self.active_source.mute,
+                        self.active_source.device.clone(),
                     ));
                 }
             }
 
             audio_client::Event::Node(node_id, node) => {
+                let device = Device {
+                    description: node.description,
+                    icon_name: node.icon_name,
+                };
                 if node.is_sink {
                     let pos = if let Some(pos) = self.sinks.id.iter().position(|&id| id == node_id)
                     {
@@ -139,8 +175,10 @@ impl Model {
                         self.sinks.id.push(node_id);
                         self.sinks.volume.push(0);
                         self.sinks.mute.push(false);
+                        self.sinks.device.push(Device::default());
                         self.sinks.id.len() - 1
                     };
+                    self.sinks.device[pos] = device;
 
                     if let Some(default_node_id) = self.default_sink
                         && default_node_id == node_id
@@ -148,6 +186,7 @@ impl Model {
                         self.sinks.active = Some(pos);
                         self.active_sink.mute = self.sinks.mute[pos];
                         self.active_sink.volume = self.sinks.volume[pos];
+                        self.active_sink.device = self.sinks.device[pos].clone();
                     }
                 } else {
                     let pos =
@@ -157,8 +196,10 @@ impl Model {
                             self.sources.id.push(node_id);
                             self.sources.volume.push(0);
                             self.sources.mute.push(false);
+                            self.sources.device.push(Device::default());
                             self.sources.id.len() - 1
                         };
+                    self.sources.device[pos] = device;
 
                     if let Some(default_node_id) = self.default_source
                         && default_node_id == node_id
@@ -166,6 +207,7 @@ impl Model {
                         self.sources.active = Some(pos);
                         self.active_source.mute = self.sources.mute[pos];
                         self.active_source.volume = self.sources.volume[pos];
+                        self.active_source.device = self.sources.device[pos].clone();
                     }
                 }
             }
@@ -2,3 +2,4 @@ pub mod app;
 pub mod audio;
 pub mod osd_indicator;
 pub mod polkit_dialog;
+pub mod scheduled_shutdown;
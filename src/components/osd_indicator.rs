@@ -1,42 +1,62 @@
-// TODO: animation to fade in/out?
-// TODO: Dismiss on click?
-
 use crate::components::app::DisplayMode;
-use crate::config;
+use crate::components::audio::Device;
+use crate::config::{self, OsdConfig, Position, Timeouts};
+use crate::fl;
+use crate::subscriptions::{mpris, upower};
 use cosmic::cctk::sctk::seat::input_method_v3::Rectangle;
+use cosmic::iced::mouse::ScrollDelta;
 use cosmic::iced::platform_specific::shell::commands::layer_surface::{
-    Anchor, KeyboardInteractivity, Layer, destroy_layer_surface, get_layer_surface,
+    Anchor, KeyboardInteractivity, Layer, destroy_layer_surface, get_layer_surface, set_anchor,
+    set_margin,
 };
 use cosmic::iced::platform_specific::shell::commands::overlap_notify::overlap_notify;
 use cosmic::iced::runtime::platform_specific::wayland::layer_surface::{
     IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
 };
 use cosmic::iced::window::Id as SurfaceId;
-use cosmic::iced::{self, Alignment, Border, Length};
+use cosmic::iced::{self, Alignment, Border, Length, Subscription, time};
 use cosmic::{Apply, Element, Task, widget};
 use cosmic_comp_config::input::TouchpadOverride;
 use futures::future::{AbortHandle, Aborted, abortable};
+use std::borrow::Cow;
+use std::path::Path;
 use std::sync::LazyLock;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub static OSD_INDICATOR_ID: LazyLock<widget::Id> =
     LazyLock::new(|| widget::Id::new("osd-indicator".to_string()));
 
-#[derive(Debug)]
+const FADE_DURATION: Duration = Duration::from_millis(180);
+const VALUE_DURATION: Duration = Duration::from_millis(120);
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+// How far the indicator slides in from its anchored edge
+const SLIDE_DISTANCE: f32 = 24.0;
+
+#[derive(Clone, Debug)]
 pub enum Params {
     DisplayBrightness(f64),      // Rung ratio k/20.0 (hotkeys)
     DisplayBrightnessExact(f64), // Exact raw ratio raw/max (slider/arbitrary)
     DisplayToggle(DisplayMode),
     DisplayNumber(u32),
     KeyboardBrightness(f64),
-    SinkVolume(u32, bool),
-    SourceVolume(u32, bool),
+    SinkVolume(u32, bool, Device),
+    SourceVolume(u32, bool, Device),
     AirplaneMode(bool),
     TouchpadEnabled(TouchpadOverride),
+    CapsLock(bool),
+    NumLock(bool),
+    /// Requested by another program through the `com.system76.CosmicOsd` interface
+    Custom {
+        icon: String,
+        value: Option<u32>,
+        label: String,
+    },
+    Media(mpris::Media),
+    Power(upower::Power),
 }
 
 impl Params {
-    fn icon_name(&self) -> &'static str {
+    fn icon_name(&self) -> &str {
         match self {
             Self::DisplayBrightness(_) | Self::DisplayBrightnessExact(_) => {
                 "display-brightness-symbolic"
@@ -49,7 +69,7 @@ impl Params {
             Self::KeyboardBrightness(_) => "keyboard-brightness-symbolic",
             Self::AirplaneMode(true) => "airplane-mode-symbolic",
             Self::AirplaneMode(false) => "airplane-mode-disabled-symbolic",
-            Self::SinkVolume(volume, muted) => {
+            Self::SinkVolume(volume, muted, _) => {
                 if *volume == 0 || *muted {
                     "audio-volume-muted-symbolic"
                 } else if *volume < 33 {
@@ -62,7 +82,7 @@ impl Params {
                     "audio-volume-overamplified-symbolic"
                 }
             }
-            Self::SourceVolume(volume, muted) => {
+            Self::SourceVolume(volume, muted, _) => {
                 if *volume == 0 || *muted {
                     "microphone-sensitivity-muted-symbolic"
                 } else if *volume < 33 {
@@ -75,9 +95,111 @@ impl Params {
             }
             Self::TouchpadEnabled(TouchpadOverride::None) => "input-touchpad-symbolic",
             Self::TouchpadEnabled(TouchpadOverride::ForceDisable) => "touchpad-disabled-symbolic",
+            Self::CapsLock(_) | Self::NumLock(_) => "input-keyboard-symbolic",
+            Self::Power(power) => power.icon_name.as_str(),
+            Self::Custom { icon, .. } => icon.as_str(),
+            Self::Media(media) => match media.action {
+                mpris::Action::Play => "media-playback-start-symbolic",
+                mpris::Action::Pause => "media-playback-pause-symbolic",
+                mpris::Action::Stop => "media-playback-stop-symbolic",
+                mpris::Action::SkipForward => "media-skip-forward-symbolic",
+                mpris::Action::SkipBackward => "media-skip-backward-symbolic",
+            },
+        }
+    }
+
+    fn label(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::CapsLock(enabled) => Some(fl!("caps-lock", enabled = enabled.to_string()).into()),
+            Self::NumLock(enabled) => Some(fl!("num-lock", enabled = enabled.to_string()).into()),
+            Self::Power(power) => {
+                let mut label = if power.plugged_in {
+                    fl!("charger-connected")
+                } else {
+                    fl!("charger-disconnected")
+                };
+                if let Some(time) = power.time {
+                    let minutes = time.as_secs() / 60;
+                    let (hours, minutes) = ((minutes / 60).to_string(), (minutes % 60).to_string());
+                    label.push_str(" · ");
+                    label.push_str(&if power.plugged_in {
+                        fl!("time-until-full", hours = hours, minutes = minutes)
+                    } else {
+                        fl!("time-until-empty", hours = hours, minutes = minutes)
+                    });
+                }
+                Some(label.into())
+            }
+            Self::SinkVolume(_, _, device) | Self::SourceVolume(_, _, device)
+                if !device.description.is_empty() =>
+            {
+                Some(device.description.as_str().into())
+            }
+            Self::Custom { label, .. } if !label.is_empty() => Some(label.as_str().into()),
+            Self::Media(media) if !media.title.is_empty() => Some(media.title.as_str().into()),
+            _ => None,
+        }
+    }
+
+    // Shown before the label, for indicators that also have their own icon
+    fn label_icon(&self) -> Option<&str> {
+        match self {
+            Self::SinkVolume(_, _, device) | Self::SourceVolume(_, _, device)
+                if !device.icon_name.is_empty() =>
+            {
+                Some(device.icon_name.as_str())
+            }
+            _ => None,
         }
     }
 
+    // Shown under the label in a smaller font
+    fn sublabel(&self) -> Option<&str> {
+        match self {
+            Self::Media(media) if !media.artist.is_empty() => Some(media.artist.as_str()),
+            _ => None,
+        }
+    }
+
+    // Image shown in place of the icon
+    fn art(&self) -> Option<&Path> {
+        match self {
+            Self::Media(media) => media.art.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the value can be changed from the indicator in interactive mode.
+    /// A muted device shows 0, and changing its volume wouldn't unmute it.
+    pub fn is_adjustable(&self) -> bool {
+        match self {
+            Self::SinkVolume(_, muted, _) | Self::SourceVolume(_, muted, _) => !muted,
+            Self::DisplayBrightness(_)
+            | Self::DisplayBrightnessExact(_)
+            | Self::KeyboardBrightness(_) => true,
+            _ => false,
+        }
+    }
+
+    fn timeout(&self, timeouts: &Timeouts) -> Duration {
+        let ms = match self {
+            Self::SinkVolume(..) | Self::SourceVolume(..) => timeouts.volume_ms,
+            Self::DisplayBrightness(_)
+            | Self::DisplayBrightnessExact(_)
+            | Self::KeyboardBrightness(_) => timeouts.brightness_ms,
+            Self::DisplayToggle(_)
+            | Self::AirplaneMode(_)
+            | Self::TouchpadEnabled(_)
+            | Self::CapsLock(_)
+            | Self::NumLock(_)
+            | Self::Media(_) => timeouts.toggle_ms,
+            Self::DisplayNumber(_) => timeouts.display_identifier_ms,
+            Self::Custom { .. } => timeouts.custom_ms,
+            Self::Power(_) => timeouts.power_ms,
+        };
+        Duration::from_millis(ms)
+    }
+
     fn value(&self) -> Option<u32> {
         match self {
             Self::DisplayBrightness(value) => {
@@ -105,14 +227,19 @@ impl Params {
                 Some(p as u32)
             }
             Self::KeyboardBrightness(value) => Some((*value * 100.) as u32),
-            Self::SinkVolume(_, true) => Some(0),
-            Self::SourceVolume(_, true) => Some(0),
-            Self::SinkVolume(value, false) => Some(*value),
-            Self::SourceVolume(value, false) => Some(*value),
+            Self::SinkVolume(_, true, _) => Some(0),
+            Self::SourceVolume(_, true, _) => Some(0),
+            Self::SinkVolume(value, false, _) => Some(*value),
+            Self::SourceVolume(value, false, _) => Some(*value),
             Self::AirplaneMode(_) => None,
             Self::TouchpadEnabled(_) => None,
             Self::DisplayToggle(_) => None,
             Self::DisplayNumber(_) => None,
+            Self::Custom { value, .. } => *value,
+            Self::CapsLock(_) => None,
+            Self::NumLock(_) => None,
+            Self::Media(_) => None,
+            Self::Power(power) => Some(power.percentage.round().clamp(0.0, 100.0) as u32),
         }
     }
 }
@@ -121,37 +248,52 @@ impl Params {
 pub enum Msg {
     Ignore,
     Close(SurfaceId),
+    Dismiss,
+    Frame(Instant),
+    Hover(bool),
+    /// Value picked with the slider or by scrolling, from 0 to the bar's maximum
+    SetValue(u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Phase {
+    FadeIn(Instant),
+    Shown,
+    FadeOut(Instant),
 }
 
 #[derive(Debug)]
 pub struct State {
     id: SurfaceId,
     params: Params,
+    output: IcedOutput,
     timer_abort: AbortHandle,
-    pub margin: (i32, i32, i32, i32),
+    margin: (i32, i32, i32, i32),
+    // Takes pointer input, with margins applied by the compositor instead of widgets
+    interactive: bool,
+    hovered: bool,
     amplification_sink: bool,
     amplification_source: bool,
+    phase: Phase,
+    // Animation clock, advanced by `Msg::Frame` while animating
+    clock: Instant,
+    // Bar progress to move away from after `replace_params`, and when that started
+    previous_progress: Option<(f32, Instant)>,
+    config: OsdConfig,
 }
 
-fn close_timer(id: SurfaceId) -> (Task<Msg>, AbortHandle) {
-    let (future, timer_abort) = abortable(async {
-        let duration = Duration::from_secs(3);
-        tokio::time::sleep(duration).await;
-    });
-    let command = cosmic::task::future(async move {
-        match future.await {
-            Ok(_) => Msg::Close(id),
-            Err(Aborted) => Msg::Ignore,
-        }
-    });
-    (command, timer_abort)
+/// Fraction of `duration` that has elapsed between `start` and `now`, from 0 to 1
+fn animation_progress(start: Instant, now: Instant, duration: Duration) -> f32 {
+    (now.saturating_duration_since(start).as_secs_f32() / duration.as_secs_f32()).clamp(0.0, 1.0)
+}
+
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
 }
 
-/// Creates a 1-second timer for display identifiers
-/// When the timer expires, it sends Msg::Close to remove the display identifier
-fn display_identifier_timer(id: SurfaceId) -> (Task<Msg>, AbortHandle) {
-    let (future, timer_abort) = abortable(async {
-        let duration = Duration::from_secs(1);
+/// Creates a timer that sends `Msg::Close` once `duration` has passed
+fn close_timer(id: SurfaceId, duration: Duration) -> (Task<Msg>, AbortHandle) {
+    let (future, timer_abort) = abortable(async move {
         tokio::time::sleep(duration).await;
     });
     let command = cosmic::task::future(async move {
@@ -163,32 +305,128 @@ fn display_identifier_timer(id: SurfaceId) -> (Task<Msg>, AbortHandle) {
     (command, timer_abort)
 }
 
+// Step `value` by 5 per scroll line, or about that for each notch of pixel scrolling
+fn scroll_value(value: u32, max: u32, delta: ScrollDelta) -> u32 {
+    let change = match delta {
+        ScrollDelta::Lines { y, .. } => y * 5.0,
+        ScrollDelta::Pixels { y, .. } => y / 4.0,
+    };
+    (value as f32 + change).round().clamp(0.0, max as f32) as u32
+}
+
+/// A progress bar `width` wide that fades with the rest of the indicator, with
+/// an optional marker at a fraction of its width
+fn progress_bar<'a>(
+    progress: f32,
+    marker: Option<f32>,
+    width: f32,
+    opacity: f32,
+) -> Element<'a, Msg> {
+    const GIRTH: f32 = 4.0;
+    let bar = |color: fn(&cosmic::Theme) -> iced::Color| {
+        cosmic::theme::Container::custom(move |theme| widget::container::Style {
+            background: Some(color(theme).scale_alpha(opacity).into()),
+            border: Border {
+                radius: (GIRTH / 2.0).into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    };
+
+    let fill = widget::container(
+        widget::space::horizontal().width(Length::Fixed(progress.clamp(0.0, 1.0) * width)),
+    )
+    .height(Length::Fixed(GIRTH))
+    .class(bar(|theme| theme.cosmic().accent_color().into()));
+    let track = widget::container(fill)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(GIRTH))
+        .class(bar(|theme| theme.cosmic().bg_divider().into()));
+
+    match marker {
+        // A gap in the bar, in the indicator's background color
+        Some(marker) => iced::widget::stack![
+            track,
+            widget::row::with_children([
+                widget::space::horizontal()
+                    .width(Length::Fixed(marker * width - 1.0))
+                    .into(),
+                widget::container(widget::space::horizontal().width(Length::Fixed(2.0)))
+                    .height(Length::Fixed(GIRTH))
+                    .class(cosmic::theme::Container::custom(move |theme| {
+                        widget::container::Style {
+                            background: Some(
+                                iced::Color::from(theme.cosmic().bg_color())
+                                    .scale_alpha(opacity)
+                                    .into(),
+                            ),
+                            ..Default::default()
+                        }
+                    }))
+                    .into(),
+            ]),
+        ]
+        .into(),
+        None => track.into(),
+    }
+}
+
+fn anchor(position: Position) -> Anchor {
+    match position {
+        Position::Top => Anchor::TOP,
+        Position::Bottom => Anchor::BOTTOM,
+        Position::Left => Anchor::LEFT,
+        Position::Right => Anchor::RIGHT,
+        Position::TopLeft => Anchor::TOP | Anchor::LEFT,
+        Position::TopRight => Anchor::TOP | Anchor::RIGHT,
+        Position::BottomLeft => Anchor::BOTTOM | Anchor::LEFT,
+        Position::BottomRight => Anchor::BOTTOM | Anchor::RIGHT,
+    }
+}
+
 impl State {
-    pub fn new(id: SurfaceId, params: Params) -> (Self, Task<Msg>) {
-        Self::new_with_output(id, params, IcedOutput::Active)
+    pub fn new(id: SurfaceId, params: Params, config: &OsdConfig) -> (Self, Task<Msg>) {
+        Self::new_with_output(id, params, IcedOutput::Active, config)
     }
 
-    pub fn new_with_output(id: SurfaceId, params: Params, output: IcedOutput) -> (Self, Task<Msg>) {
+    pub fn new_with_output(
+        id: SurfaceId,
+        params: Params,
+        output: IcedOutput,
+        config: &OsdConfig,
+    ) -> (Self, Task<Msg>) {
         let mut cmds = vec![];
 
         let is_display_number = matches!(params, Params::DisplayNumber(_));
         let anchor = if is_display_number {
             Anchor::TOP
         } else {
-            Anchor::BOTTOM
+            anchor(config.position)
         };
 
+        let interactive = config.interactive && !is_display_number;
+
         // For display numbers, set exclusive_zone to -1 so they don't block input
         // in transparent areas. For other OSDs, use default behavior.
         let exclusive_zone = if is_display_number { -1 } else { 0 };
         let margin = if is_display_number {
             // Set top margin for display identifiers
             IcedMargin {
-                top: 48,
+                top: config.margin,
                 right: 0,
                 bottom: 0,
                 left: 0,
             }
+        } else if interactive {
+            // Keep the surface the size of the pill, so it only takes input over the pill
+            let (top, right, bottom, left) = config.margins();
+            IcedMargin {
+                top,
+                right,
+                bottom,
+                left,
+            }
         } else {
             // No margin for other OSDs (they use widget-based margins)
             IcedMargin {
@@ -206,45 +444,49 @@ impl State {
             layer: Layer::Overlay,
             size: None,
             anchor,
-            output,
+            output: output.clone(),
             exclusive_zone,
             margin,
-            input_zone: Some(Vec::new()),
+            input_zone: if interactive { None } else { Some(Vec::new()) },
             ..Default::default()
         }));
 
         cmds.push(overlap_notify(id, true));
 
-        // Display numbers auto-close after 1 second, other OSDs after 3 seconds
-        let timer_abort = if is_display_number {
-            let (cmd, timer_abort) = display_identifier_timer(id);
-            cmds.push(cmd);
-            timer_abort
-        } else {
-            let (cmd, timer_abort) = close_timer(id);
-            cmds.push(cmd);
-            timer_abort
-        };
+        let (cmd, timer_abort) = close_timer(id, params.timeout(&config.timeouts));
+        cmds.push(cmd);
 
         let amplification_sink = config::amplification_sink();
         let amplification_source = config::amplification_source();
+        let clock = Instant::now();
 
         // Margin: (top, right, bottom, left)
-        // Display numbers at top, other OSDs at bottom
+        // Display numbers at top, other OSDs at their configured edge
         let margin = if is_display_number {
-            (48, 0, 0, 0) // Top margin for display numbers
+            (config.margin, 0, 0, 0) // Top margin for display numbers
         } else {
-            (0, 0, 48, 0) // Bottom margin for other OSDs
+            config.margins()
         };
 
         (
             Self {
                 id,
                 params,
+                output,
                 timer_abort,
                 margin,
+                interactive,
+                hovered: false,
                 amplification_sink,
                 amplification_source,
+                phase: if config.reduced_motion {
+                    Phase::Shown
+                } else {
+                    Phase::FadeIn(clock)
+                },
+                clock,
+                previous_progress: None,
+                config: config.clone(),
             },
             Task::batch(cmds),
         )
@@ -254,13 +496,66 @@ impl State {
         &self.params
     }
 
+    /// The output the surface was placed on
+    pub fn output(&self) -> &IcedOutput {
+        &self.output
+    }
+
+    // Apply changed settings to an indicator that is already shown
+    pub fn set_config(&mut self, config: &OsdConfig) -> Task<Msg> {
+        let moved = config.position != self.config.position;
+        self.config = config.clone();
+        if moved && !matches!(self.params, Params::DisplayNumber(_)) {
+            set_anchor(self.id, anchor(config.position))
+        } else {
+            Task::none()
+        }
+    }
+
+    // Margin around the indicator: (top, right, bottom, left)
+    pub fn set_margin(&mut self, margin: (i32, i32, i32, i32)) -> Task<Msg> {
+        if margin == self.margin {
+            return Task::none();
+        }
+        self.margin = margin;
+        if self.interactive {
+            set_margin(self.id, margin.0, margin.1, margin.2, margin.3)
+        } else {
+            Task::none()
+        }
+    }
+
     // Re-use OSD surface to show a different OSD
     // Resets close timer
     pub fn replace_params(&mut self, params: Params) -> Task<Msg> {
+        self.clock = Instant::now();
+        if !self.config.reduced_motion {
+            // Move the bar from where it is now, rather than jumping to the new value
+            if self.params.value().is_some() && params.value().is_some() {
+                self.previous_progress = Some((self.bar_progress(), self.clock));
+            }
+        }
         self.params = params;
-        // Reset timer
+        self.cancel_fade_out();
+        self.restart_timer()
+    }
+
+    // Fade back in from the current opacity if the indicator was closing
+    fn cancel_fade_out(&mut self) {
+        if let Phase::FadeOut(_) = self.phase {
+            self.clock = Instant::now();
+            let elapsed = FADE_DURATION.mul_f32(self.opacity());
+            self.phase = Phase::FadeIn(self.clock.checked_sub(elapsed).unwrap_or(self.clock));
+        }
+    }
+
+    // Start the close timer over, unless the pointer is holding the indicator open
+    fn restart_timer(&mut self) -> Task<Msg> {
         self.timer_abort.abort();
-        let (cmd, timer_abort) = close_timer(self.id);
+        if self.hovered {
+            return Task::none();
+        }
+        let (cmd, timer_abort) = close_timer(self.id, self.params.timeout(&self.config.timeouts));
         self.timer_abort = timer_abort;
         cmd
     }
@@ -273,19 +568,53 @@ impl State {
         }
 
         self.timer_abort.abort();
-        let (cmd, timer_abort) = display_identifier_timer(self.id);
+        let (cmd, timer_abort) = close_timer(self.id, self.params.timeout(&self.config.timeouts));
         self.timer_abort = timer_abort;
         cmd
     }
 
     fn max_value(&self) -> f32 {
         match self.params {
-            Params::SinkVolume(_, _) if self.amplification_sink => 150.0,
-            Params::SourceVolume(_, _) if self.amplification_source => 150.0,
+            Params::SinkVolume(..) if self.amplification_sink => 150.0,
+            Params::SourceVolume(..) if self.amplification_source => 150.0,
             _ => 100.0,
         }
     }
 
+    fn is_animating(&self) -> bool {
+        !matches!(self.phase, Phase::Shown) || self.previous_progress.is_some()
+    }
+
+    fn opacity(&self) -> f32 {
+        match self.phase {
+            Phase::FadeIn(start) => ease_out(animation_progress(start, self.clock, FADE_DURATION)),
+            Phase::Shown => 1.0,
+            Phase::FadeOut(start) => {
+                1.0 - ease_out(animation_progress(start, self.clock, FADE_DURATION))
+            }
+        }
+    }
+
+    // Offset from the resting position towards the anchored edge
+    fn slide_offset(&self) -> f32 {
+        if self.config.slide_animation && !matches!(self.params, Params::DisplayNumber(_)) {
+            (1.0 - self.opacity()) * SLIDE_DISTANCE
+        } else {
+            0.0
+        }
+    }
+
+    fn bar_progress(&self) -> f32 {
+        let target = self.params.value().unwrap_or(0) as f32 / self.max_value();
+        match self.previous_progress {
+            Some((from, start)) => {
+                from + (target - from)
+                    * ease_out(animation_progress(start, self.clock, VALUE_DURATION))
+            }
+            None => target,
+        }
+    }
+
     pub fn view(&self) -> Element<'_, Msg> {
         // Display numbers use a completely different rendering
         if let Params::DisplayNumber(display_number) = self.params {
@@ -293,6 +622,8 @@ impl State {
         }
 
         let icon = widget::icon::from_name(self.params.icon_name());
+        let opacity = self.opacity();
+        let scale = self.config.scale();
 
         // Use large radius on value-OSD to enforce pill-shape with "Round" system style
         let radius;
@@ -300,75 +631,186 @@ impl State {
         let osd_contents = if let Some(value) = self.params.value() {
             radius = cosmic::theme::active().cosmic().radius_l();
             let max_value = self.max_value();
-            let progress = value as f32 / max_value;
-
-            let osd_bar = if max_value > 100.0 {
-                widget::determinate_linear(progress).markers([1.0 / 1.5])
+            let progress = self.bar_progress();
+
+            // The slider can't be drawn translucent, so it's only used once fully
+            // faded in
+            let osd_bar: Element<'_, Msg> =
+                if self.interactive && self.params.is_adjustable() && opacity >= 1.0 {
+                    widget::slider(0..=max_value as u32, value, Msg::SetValue)
+                        .width(Length::Fixed(266.0 * scale))
+                        .into()
+                } else {
+                    // Volumes above 100% get a marker at 100%
+                    let marker = (max_value > 100.0).then_some(100.0 / max_value);
+                    progress_bar(progress, marker, 266.0 * scale, opacity)
+                };
+
+            // Labelled indicators show the label as a caption above the bar
+            let osd_bar: Element<'_, Msg> = if let Some(label) = self.params.label() {
+                let label: Element<'_, Msg> = match self.params.label_icon() {
+                    Some(label_icon) => widget::row::with_children([
+                        widget::icon::from_name(label_icon)
+                            .size((14.0 * scale) as u16)
+                            .into(),
+                        widget::text::caption(label).into(),
+                    ])
+                    .spacing(cosmic::theme::active().cosmic().space_xxs())
+                    .align_y(Alignment::Center)
+                    .width(Length::Fixed(266.0 * scale))
+                    .into(),
+                    None => widget::text::caption(label)
+                        .width(Length::Fixed(266.0 * scale))
+                        .into(),
+                };
+                widget::column::with_children([label, osd_bar])
+                    .spacing(4)
+                    .into()
             } else {
-                widget::determinate_linear(progress)
-            }
-            .girth(4)
-            .width(Length::Fixed(266.0));
+                osd_bar
+            };
 
             iced::widget::row![
-                widget::container(icon.size(20)).center_x(Length::Fixed(32.0)),
+                widget::container(icon.size((20.0 * scale) as u16))
+                    .center_x(Length::Fixed(32.0 * scale)),
                 widget::text::body(format!("{}%", value))
-                    .width(Length::Fixed(32.0))
+                    .size(14.0 * scale)
+                    .width(Length::Fixed(32.0 * scale))
                     .center(),
-                widget::space::horizontal().width(Length::Fixed(8.0)),
+                widget::space::horizontal().width(Length::Fixed(8.0 * scale)),
                 osd_bar,
             ]
             .align_y(Alignment::Center)
             .apply(widget::container)
-            .width(Length::Fixed(392.0))
-            .height(Length::Fixed(52.0))
+            .width(Length::Fixed(392.0 * scale))
+            .height(Length::Fixed(52.0 * scale))
         } else {
             radius = cosmic::theme::active().cosmic().radius_m();
-            const ICON_SIZE: u16 = 112;
-            widget::container(icon.size(ICON_SIZE))
-                .width(ICON_SIZE + 2 * cosmic::theme::active().cosmic().space_l())
-                .height(ICON_SIZE + 2 * cosmic::theme::active().cosmic().space_s())
+            let icon_size = (112.0 * scale) as u16;
+            let space_s = cosmic::theme::active().cosmic().space_s();
+            if let Some(label) = self.params.label() {
+                let (image, label): (Element<'_, Msg>, Element<'_, Msg>) = match self.params.art() {
+                    // Keep the icon next to the label when art takes its place
+                    Some(art) => (
+                        widget::icon(widget::icon::from_path(art.to_path_buf()))
+                            .size(icon_size)
+                            .into(),
+                        widget::row::with_children([
+                            icon.size((16.0 * scale) as u16).into(),
+                            widget::text::body(label).into(),
+                        ])
+                        .spacing(cosmic::theme::active().cosmic().space_xxs())
+                        .align_y(Alignment::Center)
+                        .into(),
+                    ),
+                    None => (
+                        icon.size(icon_size).into(),
+                        widget::text::body(label).center().into(),
+                    ),
+                };
+                let mut children = vec![image, label];
+                if let Some(sublabel) = self.params.sublabel() {
+                    children.push(widget::text::caption(sublabel).center().into());
+                }
+                widget::column::with_children(children)
+                    .align_x(Alignment::Center)
+                    .spacing(space_s)
+                    .apply(widget::container)
+                    .width(icon_size + 2 * cosmic::theme::active().cosmic().space_l())
+                    .padding([space_s, 0])
+            } else {
+                widget::container(icon.size(icon_size))
+                    .width(icon_size + 2 * cosmic::theme::active().cosmic().space_l())
+                    .height(icon_size + 2 * space_s)
+            }
         }
         .align_x(Alignment::Center)
         .align_y(Alignment::Center)
         .class(cosmic::theme::Container::custom(move |theme| {
             widget::container::Style {
-                text_color: Some(theme.cosmic().on_bg_color().into()),
-                background: Some(iced::Color::from(theme.cosmic().bg_color()).into()),
+                text_color: Some(
+                    iced::Color::from(theme.cosmic().on_bg_color()).scale_alpha(opacity),
+                ),
+                background: Some(
+                    iced::Color::from(theme.cosmic().bg_color())
+                        .scale_alpha(opacity)
+                        .into(),
+                ),
                 border: Border {
                     radius: radius.into(),
                     width: 1.0,
-                    color: theme.cosmic().bg_divider().into(),
+                    color: iced::Color::from(theme.cosmic().bg_divider()).scale_alpha(opacity),
                 },
                 shadow: Default::default(),
-                icon_color: Some(theme.cosmic().on_bg_color().into()),
+                icon_color: Some(
+                    iced::Color::from(theme.cosmic().on_bg_color()).scale_alpha(opacity),
+                ),
                 snap: true,
             }
         }));
 
-        let osd_contents = if self.margin.0 != 0 || self.margin.2 != 0 {
+        let osd_contents: Element<'_, Msg> = if self.interactive {
+            let mouse_area = iced::widget::mouse_area(osd_contents)
+                .on_press(Msg::Dismiss)
+                .on_enter(Msg::Hover(true))
+                .on_exit(Msg::Hover(false));
+            match self.params.value() {
+                Some(value) if self.params.is_adjustable() => {
+                    let max = self.max_value() as u32;
+                    mouse_area
+                        .on_scroll(move |delta| Msg::SetValue(scroll_value(value, max, delta)))
+                        .into()
+                }
+                _ => mouse_area.into(),
+            }
+        } else {
+            osd_contents.into()
+        };
+
+        // Interactive indicators leave their margins to the compositor
+        let (mut top, mut right, mut bottom, mut left) = if self.interactive {
+            (0., 0., 0., 0.)
+        } else {
+            (
+                self.margin.0 as f32,
+                self.margin.1 as f32,
+                self.margin.2 as f32,
+                self.margin.3 as f32,
+            )
+        };
+        // Slide by moving margin from the anchored edge to the opposite one, so the
+        // surface keeps its size
+        let slide = self.slide_offset();
+        let anchor = anchor(self.config.position);
+        if anchor.contains(Anchor::BOTTOM) {
+            let slide = slide.min(bottom);
+            (top, bottom) = (top + slide, bottom - slide);
+        } else if anchor.contains(Anchor::TOP) {
+            let slide = slide.min(top);
+            (top, bottom) = (top - slide, bottom + slide);
+        } else if anchor.contains(Anchor::LEFT) {
+            let slide = slide.min(left);
+            (left, right) = (left - slide, right + slide);
+        } else if anchor.contains(Anchor::RIGHT) {
+            let slide = slide.min(right);
+            (left, right) = (left + slide, right - slide);
+        }
+
+        let osd_contents = if top != 0. || bottom != 0. {
             widget::column::with_children([
-                widget::space::vertical()
-                    .height(self.margin.0 as f32)
-                    .into(),
-                osd_contents.into(),
-                widget::space::vertical()
-                    .height(self.margin.2 as f32)
-                    .into(),
+                widget::space::vertical().height(top).into(),
+                osd_contents,
+                widget::space::vertical().height(bottom).into(),
             ])
             .into()
         } else {
-            osd_contents.into()
+            osd_contents
         };
-        let osd_contents = if self.margin.1 != 0 || self.margin.3 != 0 {
+        let osd_contents = if left != 0. || right != 0. {
             widget::row::with_children([
-                widget::space::horizontal()
-                    .width(self.margin.1 as f32)
-                    .into(),
+                widget::space::horizontal().width(left).into(),
                 osd_contents,
-                widget::space::horizontal()
-                    .width(self.margin.3 as f32)
-                    .into(),
+                widget::space::horizontal().width(right).into(),
             ])
             .into()
         } else {
@@ -392,6 +834,7 @@ impl State {
 
         let theme = cosmic::theme::active();
         let cosmic_theme = theme.cosmic();
+        let opacity = self.opacity();
 
         let number_text = widget::text::title1(format!("{}", display_number))
             .size(TEXT_SIZE)
@@ -412,15 +855,23 @@ impl State {
             .center(Length::Fixed(square_size))
             .class(cosmic::theme::Container::custom(move |theme| {
                 widget::container::Style {
-                    text_color: Some(iced::Color::from(theme.cosmic().on_accent_color())),
-                    background: Some(iced::Color::from(theme.cosmic().accent_color()).into()),
+                    text_color: Some(
+                        iced::Color::from(theme.cosmic().on_accent_color()).scale_alpha(opacity),
+                    ),
+                    background: Some(
+                        iced::Color::from(theme.cosmic().accent_color())
+                            .scale_alpha(opacity)
+                            .into(),
+                    ),
                     border: Border {
                         radius: theme.cosmic().radius_m().into(),
                         width: 0.0,
                         color: iced::Color::TRANSPARENT,
                     },
                     shadow: Default::default(),
-                    icon_color: Some(iced::Color::from(theme.cosmic().on_accent_color())),
+                    icon_color: Some(
+                        iced::Color::from(theme.cosmic().on_accent_color()).scale_alpha(opacity),
+                    ),
                     snap: true,
                 }
             }));
@@ -432,11 +883,72 @@ impl State {
             .into()
     }
 
-    pub fn update(self, msg: Msg) -> (Option<Self>, Task<Msg>) {
+    pub fn update(mut self, msg: Msg) -> (Option<Self>, Task<Msg>) {
         log::trace!("indicator msg: {:?}", msg);
         match msg {
             Msg::Ignore => (Some(self), Task::none()),
-            Msg::Close(id) => (None, destroy_layer_surface(id)),
+            Msg::Close(id) => {
+                if self.config.reduced_motion {
+                    return (None, destroy_layer_surface(id));
+                }
+                if !matches!(self.phase, Phase::FadeOut(_)) {
+                    // Fade out from the current opacity if still fading in
+                    self.clock = Instant::now();
+                    let elapsed = FADE_DURATION.mul_f32(1.0 - self.opacity());
+                    self.phase =
+                        Phase::FadeOut(self.clock.checked_sub(elapsed).unwrap_or(self.clock));
+                }
+                (Some(self), Task::none())
+            }
+            Msg::Dismiss => {
+                self.timer_abort.abort();
+                let id = self.id;
+                self.update(Msg::Close(id))
+            }
+            Msg::Hover(hovered) => {
+                self.hovered = hovered;
+                if hovered {
+                    self.cancel_fade_out();
+                }
+                let cmd = self.restart_timer();
+                (Some(self), cmd)
+            }
+            // Applied by the app; the new value comes back through `replace_params`
+            Msg::SetValue(_) => {
+                self.cancel_fade_out();
+                let cmd = self.restart_timer();
+                (Some(self), cmd)
+            }
+            Msg::Frame(now) => {
+                self.clock = now;
+                if let Some((_, start)) = self.previous_progress
+                    && animation_progress(start, now, VALUE_DURATION) >= 1.0
+                {
+                    self.previous_progress = None;
+                }
+                match self.phase {
+                    Phase::FadeIn(start)
+                        if animation_progress(start, now, FADE_DURATION) >= 1.0 =>
+                    {
+                        self.phase = Phase::Shown;
+                    }
+                    Phase::FadeOut(start)
+                        if animation_progress(start, now, FADE_DURATION) >= 1.0 =>
+                    {
+                        return (None, destroy_layer_surface(self.id));
+                    }
+                    _ => {}
+                }
+                (Some(self), Task::none())
+            }
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Msg> {
+        if self.is_animating() {
+            time::every(FRAME_INTERVAL).map(Msg::Frame)
+        } else {
+            Subscription::none()
         }
     }
 }
@@ -2,6 +2,7 @@
 
 #![allow(clippy::single_match)]
 
+use crate::accounts::User;
 use crate::fl;
 use crate::subscriptions::polkit_agent::PolkitError;
 use crate::subscriptions::polkit_agent_helper;
@@ -22,11 +23,18 @@ pub static POLKIT_DIALOG_ID: LazyLock<widget::Id> =
 
 #[derive(Clone, Debug)]
 pub struct Params {
+    /// The user authenticating, one of `users`
     pub pw_name: String,
+    /// Users that can authenticate the action
+    pub users: Vec<User>,
     pub action_id: String,
     pub message: String,
     pub icon_name: Option<String>,
     pub details: HashMap<String, String>,
+    /// Vendor of the action, from its policy file
+    pub vendor: Option<String>,
+    /// Executable of the process asking for authorization
+    pub program: Option<String>,
     pub cookie: String,
     // XXX `Clone` bound is awkward here
     pub response_sender: Arc<Mutex<Option<oneshot::Sender<Result<(), PolkitError>>>>>,
@@ -39,27 +47,40 @@ pub enum Msg {
     Cancel,
     Layer(wayland::LayerEvent),
     Password(String),
+    SelectUser(usize),
     Sent(bool),
+    ToggleDetails,
     TogglePasswordVisibility,
 }
 
+enum PamMessage {
+    Info(String),
+    Error(String),
+}
+
 pub struct State {
     id: SurfaceId,
     pub params: Params,
     responder: Option<polkit_agent_helper::Responder>,
     password: String,
     password_visible: bool,
-    message: Option<String>, // TODO show
-    password_label: String,  // TODO
+    /// Latest message from PAM, like a locked or expired account
+    message: Option<PamMessage>,
+    /// PAM's prompt, like `Password:`
+    password_label: String,
     echo: bool,
     pub text_input_id: iced::id::Id,
     sensitive: bool,
     retries: u32,
+    /// Labelled rows of the expandable details section
+    details: Vec<(String, String)>,
+    details_visible: bool,
     // TODO: Better way to use fluent with iced?
     msg_cancel: String,
     msg_authenticate: String,
     msg_authentication_required: String,
     msg_invalid_password: String,
+    msg_details: String,
 }
 
 impl State {
@@ -86,10 +107,13 @@ impl State {
                 text_input_id,
                 sensitive: true,
                 retries: 0,
+                details: details(&params),
+                details_visible: false,
                 msg_cancel: fl!("cancel"),
                 msg_authenticate: fl!("authenticate"),
                 msg_authentication_required: fl!("authentication-required"),
                 msg_invalid_password: fl!("invalid-password"),
+                msg_details: fl!("details"),
             },
             cmd,
         )
@@ -127,10 +151,10 @@ impl State {
                     self.echo = echo;
                 }
                 polkit_agent_helper::Event::ShowError(s) => {
-                    self.message = Some(s);
+                    self.message = Some(PamMessage::Error(s));
                 }
                 polkit_agent_helper::Event::ShowDebug(s) => {
-                    self.message = Some(s);
+                    self.message = Some(PamMessage::Info(s));
                 }
                 polkit_agent_helper::Event::Complete(success) => {
                     if success {
@@ -147,6 +171,9 @@ impl State {
             },
             Msg::Authenticate => {
                 self.sensitive = false; // TODO: show spinner?
+                // PAM sends messages ahead of the prompt they belong to, so they're
+                // cleared once it's answered rather than when the next one arrives
+                self.message = None;
                 if let Some(responder) = self.responder.clone() {
                     let password = self.password.clone();
 
@@ -163,6 +190,26 @@ impl State {
             Msg::Password(password) => {
                 self.password = password;
             }
+            Msg::SelectUser(index) => {
+                if let Some(user) = self.params.users.get(index)
+                    && user.name != self.params.pw_name
+                {
+                    // The helper subscription is keyed on the user, so this starts a
+                    // new conversation as them
+                    self.params.pw_name = user.name.clone();
+                    self.responder = None;
+                    self.password.clear();
+                    self.password_label.clear();
+                    self.message = None;
+                    self.retries = 0;
+                    self.sensitive = true;
+                    let cmd = widget::text_input::focus(self.text_input_id.clone());
+                    return (Some(self), cmd);
+                }
+            }
+            Msg::ToggleDetails => {
+                self.details_visible = !self.details_visible;
+            }
             Msg::TogglePasswordVisibility => {
                 self.password_visible = !self.password_visible;
             }
@@ -204,18 +251,98 @@ impl State {
                 authenticate_button = authenticate_button.on_press(Msg::Authenticate);
             }
         }
-        let mut right_column: Vec<cosmic::Element<_>> = vec![password_input.into()];
-        if self.retries > 0 {
-            right_column.push(
-                widget::text::body(&self.msg_invalid_password)
-                    .class(cosmic::theme::Text::Color(iced::Color::from_rgb(
-                        1.0, 0.0, 0.0,
-                    )))
+        let mut right_column: Vec<cosmic::Element<_>> = Vec::new();
+        // The current user is preselected when they can authenticate, and there's
+        // no reason to use someone else's password then
+        let current_uid = uzers::get_current_uid();
+        if self.params.users.len() > 1
+            && !self.params.users.iter().any(|user| user.uid == current_uid)
+        {
+            right_column.extend(self.params.users.iter().enumerate().map(|(i, user)| {
+                let selected = user.name == self.params.pw_name;
+                let avatar: cosmic::Element<_> = match &user.icon {
+                    Some(path) => widget::icon(widget::icon::from_path(path.clone()))
+                        .size(32)
+                        .into(),
+                    None => widget::icon::from_name("avatar-default-symbolic")
+                        .size(32)
+                        .into(),
+                };
+                let mut children: Vec<cosmic::Element<_>> = vec![
+                    avatar,
+                    widget::column::with_children([
+                        widget::text::body(&user.real_name).into(),
+                        widget::text::caption(&user.name).into(),
+                    ])
                     .into(),
-            );
-        } else {
+                ];
+                if selected {
+                    children.push(widget::space::horizontal().into());
+                    children.push(
+                        widget::icon::from_name("object-select-symbolic")
+                            .size(16)
+                            .into(),
+                    );
+                }
+                let mut button = widget::button::custom(
+                    widget::row::with_children(children)
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                )
+                .width(iced::Length::Fill)
+                .class(cosmic::theme::Button::Text);
+                if self.sensitive {
+                    button = button.on_press(Msg::SelectUser(i));
+                }
+                button.into()
+            }));
+        }
+        // The usual prompt is already the placeholder, but PAM modules can ask for
+        // something else, like a one-time code
+        let label = self.password_label.trim();
+        if !label.is_empty() && label != "Password:" {
+            right_column.push(widget::text::body(label).into());
+        }
+        right_column.push(password_input.into());
+        let error = |text| {
+            widget::text::body(text).class(cosmic::theme::Text::Color(iced::Color::from_rgb(
+                1.0, 0.0, 0.0,
+            )))
+        };
+        match &self.message {
+            Some(PamMessage::Error(message)) => right_column.push(error(message).into()),
+            Some(PamMessage::Info(message)) => {
+                right_column.push(widget::text::body(message).into())
+            }
+            None => {}
+        }
+        if self.retries > 0 {
+            right_column.push(error(&self.msg_invalid_password).into());
+        } else if self.message.is_none() {
             right_column.push(widget::text::body("").into())
         }
+        right_column.push(
+            widget::button::text(&self.msg_details)
+                .trailing_icon(widget::icon::from_name(if self.details_visible {
+                    "go-up-symbolic"
+                } else {
+                    "go-down-symbolic"
+                }))
+                .on_press(Msg::ToggleDetails)
+                .into(),
+        );
+        if self.details_visible {
+            right_column.extend(self.details.iter().map(|(label, value)| {
+                widget::row::with_children([
+                    widget::text::caption(label)
+                        .width(iced::Length::Fixed(120.))
+                        .into(),
+                    widget::text::caption(value).into(),
+                ])
+                .spacing(8)
+                .into()
+            }));
+        }
         let icon = widget::icon::from_name(
             self.params
                 .icon_name
@@ -251,3 +378,33 @@ impl State {
         ])
     }
 }
+
+/// Rows of the details section: the action, who asked for it, then the details
+/// polkit passed along, like pkexec's command line or the unit systemd acts on
+fn details(params: &Params) -> Vec<(String, String)> {
+    let mut rows = vec![(fl!("polkit-action"), params.action_id.clone())];
+    if let Some(vendor) = &params.vendor {
+        rows.push((fl!("polkit-vendor"), vendor.clone()));
+    }
+    if let Some(program) = &params.program {
+        rows.push((fl!("polkit-requested-by"), program.clone()));
+    }
+
+    let mut details = params
+        .details
+        .iter()
+        // Added by polkit itself, like the subject PID resolved above
+        .filter(|(key, _)| !key.starts_with("polkit."))
+        .collect::<Vec<_>>();
+    details.sort();
+    rows.extend(details.into_iter().map(|(key, value)| {
+        let label = match key.as_str() {
+            "program" => fl!("polkit-program"),
+            "command_line" => fl!("polkit-command-line"),
+            "unit" => fl!("polkit-unit"),
+            _ => key.clone(),
+        };
+        (label, value.clone())
+    }));
+    rows
+}
@@ -0,0 +1,481 @@
+// Banner for a shutdown or restart scheduled with logind, which stays up until the
+// scheduled time and can cancel it. It follows logind's schedule, so it's updated or
+// closed when someone else replaces or cancels it, e.g. with `shutdown -c`.
+
+use crate::fl;
+use crate::logind_manager::LogindManagerProxy;
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, TimeZone};
+use cosmic::iced::futures::{FutureExt, StreamExt, stream};
+use cosmic::iced::platform_specific::shell::commands::layer_surface::{
+    Anchor, KeyboardInteractivity, Layer, destroy_layer_surface, get_layer_surface,
+};
+use cosmic::iced::runtime::platform_specific::wayland::layer_surface::{
+    IcedMargin, SctkLayerSurfaceSettings,
+};
+use cosmic::iced::window::Id as SurfaceId;
+use cosmic::iced::{self, Alignment, Border, Subscription, Task, time};
+use cosmic::{Apply, Element, widget};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+use zbus::proxy::MethodFlags;
+
+static SCHEDULED_SHUTDOWN_ID: LazyLock<widget::Id> =
+    LazyLock::new(|| widget::Id::new("scheduled-shutdown".to_string()));
+
+/// When to shut down or restart, instead of confirming right away
+#[derive(Debug, Default, Serialize, Deserialize, Clone, clap::Args)]
+pub struct Schedule {
+    #[arg(
+        long = "in",
+        value_name = "DURATION",
+        value_parser = parse_delay,
+        conflicts_with = "at",
+        help = "Schedule after a delay, e.g. 90s, 30m or 1h30m. A bare number is in minutes."
+    )]
+    pub delay: Option<Duration>,
+    #[arg(
+        long,
+        value_name = "TIME",
+        value_parser = parse_time,
+        help = "Schedule at a local time, e.g. 22:30. Times already past are tomorrow."
+    )]
+    pub at: Option<NaiveTime>,
+}
+
+impl Schedule {
+    /// Whether `--in` or `--at` was given
+    pub fn is_set(&self) -> bool {
+        self.delay.is_some() || self.at.is_some()
+    }
+
+    /// The scheduled time, or `None` to confirm and run right away. Fails if the
+    /// time can't be represented, rather than running right away instead.
+    pub fn time(&self) -> Result<Option<DateTime<Local>>, String> {
+        self.time_from(Local::now())
+    }
+
+    fn time_from<Tz: TimeZone>(&self, now: DateTime<Tz>) -> Result<Option<DateTime<Tz>>, String> {
+        if let Some(delay) = self.delay {
+            return TimeDelta::from_std(delay)
+                .ok()
+                .and_then(|delay| now.checked_add_signed(delay))
+                .map(Some)
+                .ok_or_else(|| "the delay is too long".to_string());
+        }
+
+        let Some(at) = self.at else {
+            return Ok(None);
+        };
+        let today = now.date_naive();
+        let date = if today.and_time(at) > now.naive_local() {
+            today
+        } else {
+            today
+                .succ_opt()
+                .ok_or_else(|| "the date is out of range".to_string())?
+        };
+        date.and_time(at)
+            .and_local_timezone(now.timezone())
+            .earliest()
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "{} doesn't exist on {date}, because of a daylight saving time change",
+                    at.format("%H:%M")
+                )
+            })
+    }
+}
+
+/// Parses a delay like `90s`, `30m` or `1h30m`. A bare number is in minutes,
+/// like `shutdown +30`.
+fn parse_delay(s: &str) -> Result<Duration, String> {
+    const TOO_LONG: &str = "the delay is too long";
+
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("expected a delay, e.g. 30m".to_string());
+    }
+
+    let seconds = if let Ok(minutes) = s.parse::<u64>() {
+        minutes.checked_mul(60).ok_or(TOO_LONG)?
+    } else {
+        let mut seconds: u64 = 0;
+        let mut number = String::new();
+        for c in s.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let value: u64 = number
+                .parse()
+                .map_err(|_| format!("expected a number before '{c}'"))?;
+            number.clear();
+            let unit = match c {
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(format!("unknown unit '{c}', expected h, m or s")),
+            };
+            seconds = value
+                .checked_mul(unit)
+                .and_then(|value| seconds.checked_add(value))
+                .ok_or(TOO_LONG)?;
+        }
+        if !number.is_empty() {
+            return Err(format!("missing unit after '{number}', expected h, m or s"));
+        }
+        seconds
+    };
+
+    // Zero would power off right away, without the dialog
+    if seconds == 0 {
+        return Err("the delay must be longer than 0 seconds".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .map_err(|_| format!("invalid time '{s}', expected HH:MM"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    PowerOff,
+    Reboot,
+}
+
+impl Kind {
+    /// Type passed to logind's `ScheduleShutdown`
+    fn logind_type(self) -> &'static str {
+        match self {
+            Kind::PowerOff => "poweroff",
+            Kind::Reboot => "reboot",
+        }
+    }
+
+    /// Kind of a type from logind's `ScheduledShutdown` property, including dry runs
+    fn from_logind_type(logind_type: &str) -> Option<Self> {
+        match logind_type.strip_prefix("dry-").unwrap_or(logind_type) {
+            "poweroff" | "halt" => Some(Kind::PowerOff),
+            "reboot" | "kexec" | "soft-reboot" => Some(Kind::Reboot),
+            _ => None,
+        }
+    }
+}
+
+/// Ask logind to shut down or restart at `at`, replacing any earlier schedule.
+/// `interactive` lets logind ask for authentication when its policy requires it.
+pub async fn schedule(kind: Kind, at: DateTime<Local>, interactive: bool) -> zbus::Result<()> {
+    let connection = zbus::Connection::system().await?;
+    let manager_proxy = LogindManagerProxy::new(&connection).await?;
+    // `ScheduleShutdown` has no interactive argument, unlike `PowerOff`, so it's
+    // allowed with the message flag instead
+    let flags = if interactive {
+        MethodFlags::AllowInteractiveAuth.into()
+    } else {
+        Default::default()
+    };
+    manager_proxy
+        .inner()
+        .call_with_flags::<_, _, ()>(
+            "ScheduleShutdown",
+            flags,
+            &(kind.logind_type(), at.timestamp_micros() as u64),
+        )
+        .await?;
+    Ok(())
+}
+
+async fn cancel() -> zbus::Result<bool> {
+    let connection = zbus::Connection::system().await?;
+    let manager_proxy = LogindManagerProxy::new(&connection).await?;
+    manager_proxy.cancel_scheduled_shutdown().await
+}
+
+/// logind's schedule each time it changes, starting with the current one
+async fn schedule_changes() -> zbus::Result<stream::BoxStream<'static, Msg>> {
+    let connection = zbus::Connection::system().await?;
+    let manager_proxy = LogindManagerProxy::new(&connection).await?;
+    Ok(manager_proxy
+        .receive_scheduled_shutdown_changed()
+        .await
+        .filter_map(|change| async move { change.get().await.ok() })
+        .map(|(logind_type, usec)| {
+            let at = DateTime::from_timestamp_micros(usec as i64)
+                .filter(|_| usec != 0)
+                .map(|at| at.with_timezone(&Local));
+            Msg::Scheduled(Kind::from_logind_type(&logind_type).zip(at))
+        })
+        .boxed())
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Cancel,
+    Cancelled(Result<bool, zbus::Error>),
+    /// logind's schedule changed, to `None` when it was cancelled
+    Scheduled(Option<(Kind, DateTime<Local>)>),
+    Tick,
+}
+
+pub struct State {
+    id: SurfaceId,
+    kind: Kind,
+    at: DateTime<Local>,
+    remaining: Duration,
+    cancelling: bool,
+    error: Option<String>,
+}
+
+impl State {
+    pub fn new<T: 'static>(
+        id: SurfaceId,
+        kind: Kind,
+        at: DateTime<Local>,
+        margin: i32,
+    ) -> (Self, Task<T>) {
+        let cmd = get_layer_surface(SctkLayerSurfaceSettings {
+            id,
+            keyboard_interactivity: KeyboardInteractivity::None,
+            namespace: "osd".into(),
+            layer: Layer::Overlay,
+            size: None,
+            anchor: Anchor::TOP,
+            margin: IcedMargin {
+                top: margin,
+                right: 0,
+                bottom: 0,
+                left: 0,
+            },
+            ..Default::default()
+        });
+        (
+            Self {
+                id,
+                kind,
+                at,
+                remaining: remaining(at),
+                cancelling: false,
+                error: None,
+            },
+            cmd,
+        )
+    }
+
+    pub fn update(mut self, msg: Msg) -> (Option<Self>, Task<Msg>) {
+        match msg {
+            Msg::Cancel => {
+                self.cancelling = true;
+                self.error = None;
+                return (Some(self), Task::perform(cancel(), Msg::Cancelled));
+            }
+            Msg::Cancelled(Ok(_)) => return (None, destroy_layer_surface(self.id)),
+            Msg::Cancelled(Err(err)) => {
+                log::error!("Failed to cancel scheduled shutdown: {}", err);
+                self.cancelling = false;
+                self.error = Some(err.to_string());
+            }
+            Msg::Scheduled(Some((kind, at))) => {
+                self.kind = kind;
+                self.at = at;
+                self.remaining = remaining(at);
+            }
+            Msg::Scheduled(None) => return (None, destroy_layer_surface(self.id)),
+            Msg::Tick => {
+                self.remaining = remaining(self.at);
+                if self.remaining.is_zero() {
+                    return (None, destroy_layer_surface(self.id));
+                }
+            }
+        }
+        (Some(self), Task::none())
+    }
+
+    pub fn view(&self) -> Element<'_, Msg> {
+        let theme = cosmic::theme::active();
+        let cosmic_theme = theme.cosmic();
+        let (action, icon_name) = match self.kind {
+            Kind::PowerOff => ("shutdown", "system-shutdown-symbolic"),
+            Kind::Reboot => ("restart", "system-restart-symbolic"),
+        };
+        let remaining = format_remaining(self.remaining);
+        let label = fl!(
+            "scheduled-action",
+            HashMap::from_iter(vec![("action", action), ("remaining", remaining.as_str())])
+        );
+
+        let mut cancel_button = widget::button::standard(fl!("cancel"));
+        if !self.cancelling {
+            cancel_button = cancel_button.on_press(Msg::Cancel);
+        }
+
+        let row = widget::row::with_children([
+            widget::icon::from_name(icon_name).size(20).into(),
+            widget::text::body(label).into(),
+            cancel_button.into(),
+        ])
+        .spacing(cosmic_theme.space_s())
+        .align_y(Alignment::Center);
+
+        let mut column = widget::column::with_capacity(2)
+            .push(row)
+            .spacing(cosmic_theme.space_xxs());
+        if let Some(error) = &self.error {
+            column = column.push(widget::text::caption(fl!(
+                "scheduled-cancel-failed",
+                HashMap::from_iter(vec![("error", error.as_str())])
+            )));
+        }
+
+        column
+            .apply(widget::container)
+            .padding([cosmic_theme.space_xs(), cosmic_theme.space_s()])
+            .class(cosmic::theme::Container::custom(|theme| {
+                widget::container::Style {
+                    text_color: Some(iced::Color::from(theme.cosmic().on_bg_color())),
+                    background: Some(iced::Color::from(theme.cosmic().bg_color()).into()),
+                    border: Border {
+                        radius: theme.cosmic().radius_m().into(),
+                        width: 1.0,
+                        color: iced::Color::from(theme.cosmic().bg_divider()),
+                    },
+                    shadow: Default::default(),
+                    icon_color: Some(iced::Color::from(theme.cosmic().on_bg_color())),
+                    snap: true,
+                }
+            }))
+            .apply(|container| widget::autosize::autosize(container, SCHEDULED_SHUTDOWN_ID.clone()))
+            .min_width(1.)
+            .min_height(1.)
+            .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Msg> {
+        Subscription::batch([
+            time::every(Duration::from_secs(1)).map(|_| Msg::Tick),
+            Subscription::run_with("scheduled-shutdown", |_| {
+                async {
+                    match schedule_changes().await {
+                        Ok(changes) => changes,
+                        Err(err) => {
+                            log::debug!("Failed to watch the scheduled shutdown: {}", err);
+                            stream::pending().boxed()
+                        }
+                    }
+                }
+                .flatten_stream()
+            }),
+        ])
+    }
+}
+
+fn remaining(at: DateTime<Local>) -> Duration {
+    (at - Local::now()).to_std().unwrap_or_default()
+}
+
+/// Formats as `M:SS`, or `H:MM:SS` from an hour up
+fn format_remaining(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn parses_delays() {
+        assert_eq!(parse_delay("30"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_delay(" 90s "), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_delay("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_delay("1m1m"), Ok(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn rejects_invalid_delays() {
+        for delay in ["", "  ", "0", "0m", "0h0s", "h", "30x", "30m5", "-5"] {
+            assert!(parse_delay(delay).is_err(), "{delay:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_delays() {
+        assert!(parse_delay(&u64::MAX.to_string()).is_err());
+        assert!(parse_delay(&format!("{}h", u64::MAX / 2)).is_err());
+        assert!(parse_delay(&format!("{}s{}s", u64::MAX, u64::MAX)).is_err());
+    }
+
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-03-08T12:00:00+02:00").unwrap()
+    }
+
+    #[test]
+    fn schedules_after_delay() {
+        let schedule = Schedule {
+            delay: Some(Duration::from_secs(90)),
+            at: None,
+        };
+        assert_eq!(
+            schedule.time_from(now()),
+            Ok(Some(now() + TimeDelta::seconds(90)))
+        );
+    }
+
+    #[test]
+    fn schedules_at_time_today_or_tomorrow() {
+        let at = |time: &str| Schedule {
+            delay: None,
+            at: Some(parse_time(time).unwrap()),
+        };
+        assert_eq!(
+            at("22:30").time_from(now()),
+            Ok(Some(
+                DateTime::parse_from_rfc3339("2026-03-08T22:30:00+02:00").unwrap()
+            ))
+        );
+        assert_eq!(
+            at("08:00").time_from(now()),
+            Ok(Some(
+                DateTime::parse_from_rfc3339("2026-03-09T08:00:00+02:00").unwrap()
+            ))
+        );
+        assert_eq!(
+            at("12:00").time_from(now()),
+            Ok(Some(
+                DateTime::parse_from_rfc3339("2026-03-09T12:00:00+02:00").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn unset_schedule_runs_right_away() {
+        assert_eq!(Schedule::default().time_from(now()), Ok(None));
+    }
+
+    #[test]
+    fn reads_logind_types() {
+        assert_eq!(Kind::from_logind_type("poweroff"), Some(Kind::PowerOff));
+        assert_eq!(Kind::from_logind_type("dry-reboot"), Some(Kind::Reboot));
+        assert_eq!(Kind::from_logind_type(""), None);
+    }
+
+    #[test]
+    fn unrepresentable_delay_is_an_error() {
+        let schedule = Schedule {
+            delay: Some(Duration::from_secs(u64::MAX)),
+            at: None,
+        };
+        assert!(schedule.time_from(now()).is_err());
+    }
+}
@@ -1,9 +1,13 @@
-use cosmic_config::{Config, ConfigGet};
+use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic_config::{Config, ConfigGet, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
 
 const AUDIO_CONFIG: &str = "com.system76.CosmicAudio";
 const AMPLIFICATION_SINK: &str = "amplification_sink";
 const AMPLIFICATION_SOURCE: &str = "amplification_source";
 
+pub const OSD_CONFIG: &str = "com.system76.CosmicOsd";
+
 pub fn amplification_sink() -> bool {
     Config::new(AUDIO_CONFIG, 1)
         .ok()
@@ -17,3 +21,306 @@ pub fn amplification_source() -> bool {
         .and_then(|config| config.get::<bool>(AMPLIFICATION_SOURCE).ok())
         .unwrap_or(false)
 }
+
+pub fn osd_config() -> OsdConfig {
+    match Config::new(OSD_CONFIG, OsdConfig::VERSION) {
+        Ok(config) => match OsdConfig::get_entry(&config) {
+            Ok(entry) => entry,
+            Err((errors, entry)) => {
+                for err in errors {
+                    log::debug!("Failed to read {}: {}", OSD_CONFIG, err);
+                }
+                entry
+            }
+        },
+        Err(err) => {
+            log::error!("Failed to open {}: {}", OSD_CONFIG, err);
+            OsdConfig::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, CosmicConfigEntry)]
+#[version = 1]
+#[serde(default)]
+pub struct OsdConfig {
+    /// Edge or corner of the output that indicators are anchored to
+    pub position: Position,
+    /// Distance between indicators and the edges they are anchored to, in logical pixels
+    pub margin: i32,
+    pub timeouts: Timeouts,
+    /// Multiplier for the size of indicators
+    pub scale: f32,
+    /// Show and hide indicators without animating them
+    pub reduced_motion: bool,
+    /// Slide indicators in from their anchored edge while fading in
+    pub slide_animation: bool,
+    /// Let indicators take pointer input: clicking dismisses one, hovering keeps it shown,
+    /// and scrolling or dragging the bar changes volume and brightness
+    ///
+    /// Indicators don't slide in while this is enabled. Changes apply to the next indicator.
+    pub interactive: bool,
+    /// Which output(s) indicators are shown on
+    pub output: OutputPolicy,
+    /// Connector name of the output used by [`OutputPolicy::Primary`], like `DP-1`
+    ///
+    /// Falls back to the first internal display when unset or disconnected.
+    pub primary_output: Option<String>,
+    pub lock_keys: LockKeys,
+    pub actions: Actions,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            margin: 48,
+            timeouts: Timeouts::default(),
+            scale: 1.0,
+            reduced_motion: false,
+            slide_animation: true,
+            interactive: false,
+            output: OutputPolicy::default(),
+            primary_output: None,
+            lock_keys: LockKeys::default(),
+            actions: Actions::default(),
+        }
+    }
+}
+
+impl OsdConfig {
+    /// Base margin around indicators: (top, right, bottom, left)
+    pub fn margins(&self) -> (i32, i32, i32, i32) {
+        let m = self.margin;
+        match self.position {
+            Position::Top => (m, 0, 0, 0),
+            Position::Bottom => (0, 0, m, 0),
+            Position::Left => (0, 0, 0, m),
+            Position::Right => (0, m, 0, 0),
+            Position::TopLeft => (m, 0, 0, m),
+            Position::TopRight => (m, m, 0, 0),
+            Position::BottomLeft => (0, 0, m, m),
+            Position::BottomRight => (0, m, m, 0),
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale.clamp(0.5, 3.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Position {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputPolicy {
+    /// The output the compositor considers active
+    #[default]
+    Active,
+    /// The output under the pointer
+    ///
+    /// Wayland clients only see the pointer over their own surfaces, so this is the
+    /// output where the pointer last entered one of ours, like an interactive
+    /// indicator or a display identifier. Until then, the active output is used.
+    Pointer,
+    /// The output named by `primary_output`
+    Primary,
+    /// Every connected output
+    All,
+}
+
+/// Lock keys to show an indicator for when toggled, for keyboards without lock LEDs
+///
+/// The lock state is read from the kernel's `input*::capslock` and `input*::numlock`
+/// LED nodes in `/sys/class/leds`, which exist for every keyboard whether or not it
+/// has physical LEDs. Without them no indicator is shown. They're polled about once a
+/// second, and only while at least one key is enabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockKeys {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+impl LockKeys {
+    pub fn any(&self) -> bool {
+        self.caps_lock || self.num_lock
+    }
+}
+
+/// What happens when each power action is requested
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Actions {
+    pub log_out: ActionPolicy,
+    pub restart: ActionPolicy,
+    pub shutdown: ActionPolicy,
+    pub enter_bios: ActionPolicy,
+    pub suspend: ActionPolicy,
+    pub hibernate: ActionPolicy,
+    pub hybrid_sleep: ActionPolicy,
+    #[serde(deserialize_with = "LockScreenPolicy::deserialize_policy")]
+    pub lock_screen: ActionPolicy,
+}
+
+impl Default for Actions {
+    fn default() -> Self {
+        Self {
+            log_out: ActionPolicy::default(),
+            restart: ActionPolicy::default(),
+            shutdown: ActionPolicy::default(),
+            enter_bios: ActionPolicy::default(),
+            suspend: ActionPolicy::default(),
+            hibernate: ActionPolicy::default(),
+            hybrid_sleep: ActionPolicy::default(),
+            lock_screen: ActionPolicy::lock_screen(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionPolicy {
+    /// Ask for confirmation before running the action
+    pub confirm: bool,
+    /// Seconds until the confirmation dialog times out, or 0 to wait indefinitely
+    pub countdown: u32,
+    pub on_timeout: OnTimeout,
+}
+
+impl Default for ActionPolicy {
+    fn default() -> Self {
+        Self {
+            confirm: true,
+            countdown: 60,
+            on_timeout: OnTimeout::Perform,
+        }
+    }
+}
+
+impl ActionPolicy {
+    /// Locking the screen is harmless, so it runs right away
+    fn lock_screen() -> Self {
+        Self {
+            confirm: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// `ActionPolicy` as read for `lock_screen`, so that fields left out keep the lock
+/// screen's defaults instead of the other actions'
+#[derive(Deserialize)]
+#[serde(default)]
+struct LockScreenPolicy {
+    confirm: bool,
+    countdown: u32,
+    on_timeout: OnTimeout,
+}
+
+impl Default for LockScreenPolicy {
+    fn default() -> Self {
+        let ActionPolicy {
+            confirm,
+            countdown,
+            on_timeout,
+        } = ActionPolicy::lock_screen();
+        Self {
+            confirm,
+            countdown,
+            on_timeout,
+        }
+    }
+}
+
+impl LockScreenPolicy {
+    fn deserialize_policy<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ActionPolicy, D::Error> {
+        let Self {
+            confirm,
+            countdown,
+            on_timeout,
+        } = Self::deserialize(deserializer)?;
+        Ok(ActionPolicy {
+            confirm,
+            countdown,
+            on_timeout,
+        })
+    }
+}
+
+/// What the confirmation dialog does when its countdown runs out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnTimeout {
+    #[default]
+    Perform,
+    Cancel,
+}
+
+/// How long each kind of indicator stays on screen, in milliseconds
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    /// Sink and source volume
+    pub volume_ms: u64,
+    /// Display and keyboard brightness
+    pub brightness_ms: u64,
+    /// Icon-only indicators, like airplane mode, touchpad and lock keys
+    pub toggle_ms: u64,
+    /// Display numbers shown by `identify-displays`
+    pub display_identifier_ms: u64,
+    /// Indicators shown through the D-Bus interface
+    pub custom_ms: u64,
+    /// Charger connected or disconnected
+    pub power_ms: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            volume_ms: 3000,
+            brightness_ms: 3000,
+            toggle_ms: 3000,
+            display_identifier_ms: 1000,
+            custom_ms: 3000,
+            power_ms: 3000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_lock_screen_policy_keeps_its_defaults() {
+        let actions: Actions =
+            serde_json::from_str(r#"{"lock_screen": {"countdown": 10}}"#).unwrap();
+        assert_eq!(
+            actions.lock_screen,
+            ActionPolicy {
+                countdown: 10,
+                ..ActionPolicy::lock_screen()
+            }
+        );
+        assert!(!actions.lock_screen.confirm);
+    }
+
+    #[test]
+    fn partial_policy_keeps_the_default_confirmation() {
+        let actions: Actions = serde_json::from_str(r#"{"shutdown": {"countdown": 10}}"#).unwrap();
+        assert!(actions.shutdown.confirm);
+        assert_eq!(actions.lock_screen, ActionPolicy::lock_screen());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Apps that block power actions, from logind and the GNOME session manager.
+
+use crate::logind_manager::LogindManagerProxy;
+use crate::session_manager::SessionManagerProxy;
+use cosmic::iced::Subscription;
+use cosmic::iced::futures::{FutureExt, StreamExt, stream};
+use std::hash::Hash;
+use zbus::proxy::CacheProperties;
+use zbus::{Connection, proxy};
+
+// `org.gnome.SessionManager` inhibit flags
+const GNOME_INHIBIT_LOGOUT: u32 = 1;
+const GNOME_INHIBIT_SUSPEND: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    LogOut,
+    Shutdown,
+    Sleep,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inhibitor {
+    pub app: String,
+    pub icon_name: String,
+    pub reason: String,
+    /// A logind `block-weak` inhibitor, which logind lets through when the action is
+    /// requested explicitly, like from this dialog, so it doesn't stop the action
+    pub weak: bool,
+}
+
+#[proxy(
+    interface = "org.gnome.SessionManager.Inhibitor",
+    default_service = "org.gnome.SessionManager"
+)]
+trait GnomeInhibitor {
+    fn get_app_id(&self) -> zbus::Result<String>;
+    fn get_reason(&self) -> zbus::Result<String>;
+    fn get_flags(&self) -> zbus::Result<u32>;
+}
+
+/// Apps currently blocking actions of this kind, asked over the system and session
+/// connections. Either source may be missing, so failures are logged and skipped.
+pub async fn blocking(
+    kind: Kind,
+    system: Connection,
+    session: Option<Connection>,
+) -> Vec<Inhibitor> {
+    let mut inhibitors = Vec::new();
+    match logind(kind, &system).await {
+        Ok(list) => inhibitors.extend(list),
+        Err(err) => log::debug!("Failed to list logind inhibitors: {}", err),
+    }
+    if let Some(session) = session {
+        match gnome(kind, &session).await {
+            Ok(list) => inhibitors.extend(list),
+            Err(err) => log::debug!("Failed to list session manager inhibitors: {}", err),
+        }
+    }
+    inhibitors
+}
+
+/// Fires whenever an app takes or releases a blocking inhibitor, as logind's
+/// `BlockInhibited` or the session manager's `InhibitedActions` change
+pub fn changes(system: Connection, session: Option<Connection>) -> Subscription<()> {
+    struct Wrapper {
+        id: &'static str,
+        system: Connection,
+        session: Option<Connection>,
+    }
+
+    impl Hash for Wrapper {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    Subscription::run_with(
+        Wrapper {
+            id: "inhibitor-changes",
+            system,
+            session,
+        },
+        |Wrapper {
+             id: _id,
+             system,
+             session,
+         }| {
+            let (system, session) = (system.clone(), session.clone());
+            async move {
+                let logind = match logind_changes(&system).await {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        log::debug!("Failed to watch logind inhibitors: {}", err);
+                        stream::pending().boxed()
+                    }
+                };
+                let gnome = match session {
+                    Some(session) => match gnome_changes(&session).await {
+                        Ok(changes) => changes,
+                        Err(err) => {
+                            log::debug!("Failed to watch session manager inhibitors: {}", err);
+                            stream::pending().boxed()
+                        }
+                    },
+                    None => stream::pending().boxed(),
+                };
+                stream::select(logind, gnome)
+            }
+            .flatten_stream()
+        },
+    )
+}
+
+async fn logind_changes(connection: &Connection) -> zbus::Result<stream::BoxStream<'static, ()>> {
+    let manager = LogindManagerProxy::new(connection).await?;
+    // The first item repeats the current value
+    Ok(manager
+        .receive_block_inhibited_changed()
+        .await
+        .skip(1)
+        .map(|_| ())
+        .boxed())
+}
+
+async fn gnome_changes(connection: &Connection) -> zbus::Result<stream::BoxStream<'static, ()>> {
+    let manager = SessionManagerProxy::new(connection).await?;
+    // The first item repeats the current value
+    Ok(manager
+        .receive_inhibited_actions_changed()
+        .await
+        .skip(1)
+        .map(|_| ())
+        .boxed())
+}
+
+async fn logind(kind: Kind, connection: &Connection) -> zbus::Result<Vec<Inhibitor>> {
+    let what = match kind {
+        Kind::Shutdown => "shutdown",
+        Kind::Sleep => "sleep",
+        Kind::LogOut => return Ok(Vec::new()),
+    };
+
+    let manager = LogindManagerProxy::new(connection).await?;
+    Ok(manager
+        .list_inhibitors()
+        .await?
+        .into_iter()
+        // "delay" inhibitors only hold the action back for a few seconds
+        .filter(|(whats, _, _, mode, _, _)| {
+            matches!(mode.as_str(), "block" | "block-weak") && whats.split(':').any(|w| w == what)
+        })
+        .map(|(_, who, why, mode, _, _)| Inhibitor {
+            icon_name: who.to_lowercase().replace(' ', "-"),
+            app: who,
+            reason: why,
+            weak: mode == "block-weak",
+        })
+        .collect())
+}
+
+async fn gnome(kind: Kind, connection: &Connection) -> zbus::Result<Vec<Inhibitor>> {
+    let flag = match kind {
+        Kind::LogOut | Kind::Shutdown => GNOME_INHIBIT_LOGOUT,
+        Kind::Sleep => GNOME_INHIBIT_SUSPEND,
+    };
+
+    let manager = SessionManagerProxy::new(connection).await?;
+    let mut inhibitors = Vec::new();
+    for path in manager.get_inhibitors().await? {
+        // Only methods are used, so skip fetching its properties
+        let inhibitor = GnomeInhibitorProxy::builder(connection)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        if inhibitor.get_flags().await? & flag == 0 {
+            continue;
+        }
+        let app_id = inhibitor.get_app_id().await?;
+        let app_id = app_id.trim_end_matches(".desktop");
+        inhibitors.push(Inhibitor {
+            app: app_id.rsplit('.').next().unwrap_or(app_id).to_string(),
+            icon_name: app_id.to_string(),
+            reason: inhibitor.get_reason().await?,
+            weak: false,
+        });
+    }
+    Ok(inhibitors)
+}
@@ -0,0 +1,54 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait LogindManager {
+    fn can_power_off(&self) -> zbus::Result<String>;
+
+    fn can_reboot(&self) -> zbus::Result<String>;
+
+    fn can_suspend(&self) -> zbus::Result<String>;
+
+    fn can_hibernate(&self) -> zbus::Result<String>;
+
+    fn can_hybrid_sleep(&self) -> zbus::Result<String>;
+
+    fn can_reboot_to_firmware_setup(&self) -> zbus::Result<String>;
+
+    fn can_reboot_to_boot_loader_entry(&self) -> zbus::Result<String>;
+
+    fn can_reboot_to_boot_loader_menu(&self) -> zbus::Result<String>;
+
+    fn cancel_scheduled_shutdown(&self) -> zbus::Result<bool>;
+
+    fn set_reboot_to_boot_loader_entry(&self, boot_loader_entry: &str) -> zbus::Result<()>;
+
+    fn set_reboot_to_boot_loader_menu(&self, timeout: u64) -> zbus::Result<()>;
+
+    /// what, who, why, mode, uid, pid
+    #[allow(clippy::type_complexity)]
+    fn list_inhibitors(&self) -> zbus::Result<Vec<(String, String, String, String, u32, u32)>>;
+
+    /// session ID, uid, user name, seat ID, session path
+    #[allow(clippy::type_complexity)]
+    fn list_sessions(
+        &self,
+    ) -> zbus::Result<Vec<(String, u32, String, String, zbus::zvariant::OwnedObjectPath)>>;
+
+    #[zbus(property)]
+    fn boot_loader_entries(&self) -> zbus::Result<Vec<String>>;
+
+    /// Type and time in microseconds of the scheduled shutdown, or an empty type and 0
+    #[zbus(property)]
+    fn scheduled_shutdown(&self) -> zbus::Result<(String, u64)>;
+
+    /// Colon-separated actions blocked by inhibitors
+    #[zbus(property)]
+    fn block_inhibited(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,28 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait LogindSession {
+    fn lock(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn class(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn remote(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn remote_host(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "TTY")]
+    fn tty(&self) -> zbus::Result<String>;
+}
@@ -1,13 +1,20 @@
-use i18n_embed::DesktopLanguageRequester;
 use i18n_embed::fluent::{FluentLanguageLoader, fluent_language_loader};
+use i18n_embed::unic_langid::LanguageIdentifier;
+use i18n_embed::{DesktopLanguageRequester, I18nEmbedError};
 use rust_embed::RustEmbed;
 use std::sync::LazyLock;
 
+mod accounts;
 mod components;
 mod config;
 pub mod cosmic_session;
+mod inhibitors;
+pub mod logind_manager;
+pub mod logind_session;
 pub mod session_manager;
+mod sessions;
 mod subscriptions;
+mod updates;
 
 pub static LANG_LOADER: LazyLock<FluentLanguageLoader> =
     LazyLock::new(|| fluent_language_loader!());
@@ -27,10 +34,14 @@ macro_rules! fl {
     }};
 }
 
+/// Load the translations best matching `requested_languages`, most preferred first
+pub fn select_languages(requested_languages: &[LanguageIdentifier]) -> Result<(), I18nEmbedError> {
+    i18n_embed::select(&*LANG_LOADER, &Localizations, requested_languages).map(|_| ())
+}
+
 fn main() {
     let requested_languages = DesktopLanguageRequester::requested_languages();
-    i18n_embed::select(&*LANG_LOADER, &Localizations, &requested_languages)
-        .expect("Failed to load languages");
+    select_languages(&requested_languages).expect("Failed to load languages");
 
     env_logger::init();
     components::app::main().unwrap();
@@ -0,0 +1,86 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Other users' sessions, which a shutdown or restart would end.
+
+use crate::logind_manager::LogindManagerProxy;
+use crate::logind_session::LogindSessionProxy;
+use zbus::Connection;
+use zbus::zvariant::OwnedObjectPath;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub user: String,
+    pub remote: bool,
+    /// The remote host, or the seat or TTY of a local session
+    pub location: String,
+}
+
+/// Sessions of users other than the current one. Failures are logged, and treated
+/// as nobody else being logged in.
+pub async fn others() -> Vec<Session> {
+    match list().await {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            log::debug!("Failed to list sessions: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+async fn list() -> zbus::Result<Vec<Session>> {
+    let connection = Connection::system().await?;
+    let manager = LogindManagerProxy::new(&connection).await?;
+    let uid = uzers::get_current_uid();
+
+    let mut sessions = Vec::new();
+    for (_, session_uid, user, seat, path) in manager.list_sessions().await? {
+        if session_uid == uid {
+            continue;
+        }
+        // Sessions can end while they're being listed
+        let session = match session(&connection, path, user, seat).await {
+            Ok(Some(session)) => session,
+            Ok(None) => continue,
+            Err(err) => {
+                log::debug!("Failed to look up a session: {}", err);
+                continue;
+            }
+        };
+        if !sessions.contains(&session) {
+            sessions.push(session);
+        }
+    }
+    Ok(sessions)
+}
+
+/// The session at `path`, unless it's one with no one to warn
+async fn session(
+    connection: &Connection,
+    path: OwnedObjectPath,
+    user: String,
+    seat: String,
+) -> zbus::Result<Option<Session>> {
+    let session = LogindSessionProxy::builder(connection)
+        .path(path)?
+        .build()
+        .await?;
+    // Greeters and background sessions, like cron jobs, have no one to warn
+    if !session.class().await?.starts_with("user") {
+        return Ok(None);
+    }
+
+    let remote = session.remote().await.unwrap_or(false);
+    let location = if remote {
+        session.remote_host().await.unwrap_or_default()
+    } else if !seat.is_empty() {
+        seat
+    } else {
+        session.tty().await.unwrap_or_default()
+    };
+    Ok(Some(Session {
+        user,
+        remote,
+        location,
+    }))
+}
@@ -1,52 +1,63 @@
 // TODO: Handle loss of connection, name?
 
+use crate::components::osd_indicator;
 use cosmic::iced;
 use futures::stream;
+use tokio::sync::mpsc;
 
 static NAME: &str = "com.system76.CosmicOsd";
+static OBJECT_PATH: &str = "/com/system76/CosmicOsd";
 
 #[derive(Clone, Debug)]
 pub enum Event {
     Connection(zbus::Connection),
     SystemConnection(zbus::Connection),
+    Indicator(osd_indicator::Params),
     Error(&'static str, zbus::Error),
 }
 
 enum State {
     Start,
-    CreatedConnection,
-    CreatedSystemConnection,
+    CreatedConnection(mpsc::Receiver<Event>),
+    CreatedSystemConnection(mpsc::Receiver<Event>),
 }
 
 pub fn subscription() -> iced::Subscription<Event> {
     iced::Subscription::run_with("dbus-service", |_| {
         stream::unfold(State::Start, |state| async move {
             match state {
-                State::Start => Some((
-                    result_to_event(
-                        connection().await,
-                        "create session connection",
-                        Event::Connection,
-                    ),
-                    State::CreatedConnection,
-                )),
-                State::CreatedConnection => Some((
+                State::Start => {
+                    let (sender, receiver) = mpsc::channel(32);
+                    Some((
+                        result_to_event(
+                            connection(sender).await,
+                            "create session connection",
+                            Event::Connection,
+                        ),
+                        State::CreatedConnection(receiver),
+                    ))
+                }
+                State::CreatedConnection(receiver) => Some((
                     result_to_event(
                         system_connection().await,
                         "create system connection",
                         Event::SystemConnection,
                     ),
-                    State::CreatedSystemConnection,
+                    State::CreatedSystemConnection(receiver),
                 )),
-                State::CreatedSystemConnection => iced::futures::future::pending().await,
+                State::CreatedSystemConnection(mut receiver) => match receiver.recv().await {
+                    Some(event) => Some((event, State::CreatedSystemConnection(receiver))),
+                    None => iced::futures::future::pending().await,
+                },
             }
         })
     })
 }
 
-async fn connection() -> zbus::Result<zbus::Connection> {
+async fn connection(sender: mpsc::Sender<Event>) -> zbus::Result<zbus::Connection> {
     zbus::connection::Builder::session()?
         .name(NAME)?
+        .serve_at(OBJECT_PATH, CosmicOsd { sender })?
         .build()
         .await
 }
@@ -61,3 +72,53 @@ fn result_to_event<T>(res: zbus::Result<T>, context: &'static str, f: fn(T) -> E
         Err(err) => Event::Error(context, err),
     }
 }
+
+/// Lets other programs show indicators with the same look as the built-in ones.
+struct CosmicOsd {
+    sender: mpsc::Sender<Event>,
+}
+
+#[zbus::interface(name = "com.system76.CosmicOsd")]
+impl CosmicOsd {
+    /// Show an indicator with a progress bar filled to `value` out of `max`.
+    async fn show_indicator(
+        &self,
+        icon: String,
+        value: u32,
+        max: u32,
+        label: String,
+    ) -> zbus::fdo::Result<()> {
+        if max == 0 {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "max must be greater than zero".to_string(),
+            ));
+        }
+
+        let value = (value.min(max) as f64 / max as f64 * 100.0).round() as u32;
+        self.show(icon, Some(value), label).await
+    }
+
+    /// Show an icon-only indicator, like the airplane mode one.
+    async fn show_toggle(&self, icon: String, label: String) -> zbus::fdo::Result<()> {
+        self.show(icon, None, label).await
+    }
+}
+
+impl CosmicOsd {
+    async fn show(&self, icon: String, value: Option<u32>, label: String) -> zbus::fdo::Result<()> {
+        if icon.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs(
+                "icon must not be empty".to_string(),
+            ));
+        }
+
+        self.sender
+            .send(Event::Indicator(osd_indicator::Params::Custom {
+                icon,
+                value,
+                label,
+            }))
+            .await
+            .map_err(|_| zbus::fdo::Error::Failed("OSD is shutting down".to_string()))
+    }
+}
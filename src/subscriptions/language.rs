@@ -0,0 +1,58 @@
+// The user's language each time it's changed in Settings, as languages to negotiate
+// the translations with
+
+use crate::accounts;
+use cosmic::iced::Subscription;
+use cosmic::iced::futures::{FutureExt, StreamExt, stream};
+use i18n_embed::DesktopLanguageRequester;
+use i18n_embed::unic_langid::LanguageIdentifier;
+use std::hash::Hash;
+
+pub fn subscription(system_connection: zbus::Connection) -> Subscription<Vec<LanguageIdentifier>> {
+    struct Wrapper {
+        id: &'static str,
+        conn: zbus::Connection,
+    }
+
+    impl Hash for Wrapper {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    Subscription::run_with(
+        Wrapper {
+            id: "language",
+            conn: system_connection,
+        },
+        |Wrapper { id: _id, conn }| {
+            let connection = conn.clone();
+            async move {
+                match accounts::language_changes(&connection).await {
+                    Ok(languages) => languages
+                        .map(|language| requested_languages(&language))
+                        .boxed(),
+                    Err(err) => {
+                        log::debug!("Failed to watch the user's language: {}", err);
+                        stream::pending().boxed()
+                    }
+                }
+            }
+            .flatten_stream()
+        },
+    )
+}
+
+/// Languages to negotiate for an AccountsService language like `de_DE.UTF-8`,
+/// falling back to the desktop's
+fn requested_languages(language: &str) -> Vec<LanguageIdentifier> {
+    let language = language
+        .split(['.', '@'])
+        .next()
+        .filter(|language| !language.is_empty())
+        .and_then(|language| language.replace('_', "-").parse().ok());
+    language
+        .into_iter()
+        .chain(DesktopLanguageRequester::requested_languages())
+        .collect()
+}
@@ -0,0 +1,102 @@
+// Caps Lock and Num Lock state, read from the keyboard LEDs in sysfs
+//
+// Layer surfaces without keyboard focus don't receive modifier updates, and the input
+// devices aren't readable by the user, but the kernel keeps the lock LEDs in sync for
+// every keyboard, including ones without physical LEDs. Nothing notifies on LED
+// changes, so they're polled.
+
+use crate::config::LockKeys;
+use cosmic::iced::Subscription;
+use futures::stream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const LEDS_PATH: &str = "/sys/class/leds";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Polls between rescans of the LEDs, to pick up keyboards plugged in since
+const RESCAN_POLLS: u32 = 30;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    CapsLock(bool),
+    NumLock(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Locks {
+    caps_lock: bool,
+    num_lock: bool,
+}
+
+/// Brightness files of the lock LEDs of every keyboard
+#[derive(Default)]
+struct Leds {
+    caps_lock: Vec<PathBuf>,
+    num_lock: Vec<PathBuf>,
+}
+
+impl Leds {
+    /// Finds the LEDs of the enabled lock keys
+    fn scan(keys: &LockKeys) -> Self {
+        let mut leds = Self::default();
+        let Ok(entries) = std::fs::read_dir(LEDS_PATH) else {
+            return leds;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if keys.caps_lock && name.ends_with("::capslock") {
+                leds.caps_lock.push(entry.path().join("brightness"));
+            } else if keys.num_lock && name.ends_with("::numlock") {
+                leds.num_lock.push(entry.path().join("brightness"));
+            }
+        }
+        leds
+    }
+
+    fn read(&self) -> Locks {
+        // Lock keys count as on if any keyboard has them on
+        Locks {
+            caps_lock: self.caps_lock.iter().any(|led| led_on(led)),
+            num_lock: self.num_lock.iter().any(|led| led_on(led)),
+        }
+    }
+}
+
+/// Watches the lock keys enabled in `keys`
+pub fn subscription(keys: LockKeys) -> Subscription<Event> {
+    Subscription::run_with(keys, |keys| {
+        let keys = keys.clone();
+        let leds = Leds::scan(&keys);
+        let locks = leds.read();
+        stream::unfold(
+            (keys, leds, locks, 0, Vec::new()),
+            |(keys, mut leds, mut locks, mut polls, mut pending)| async move {
+                loop {
+                    if let Some(event) = pending.pop() {
+                        return Some((event, (keys, leds, locks, polls, pending)));
+                    }
+
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    polls += 1;
+                    if polls >= RESCAN_POLLS {
+                        polls = 0;
+                        leds = Leds::scan(&keys);
+                    }
+                    let current = leds.read();
+                    if current.caps_lock != locks.caps_lock {
+                        pending.push(Event::CapsLock(current.caps_lock));
+                    }
+                    if current.num_lock != locks.num_lock {
+                        pending.push(Event::NumLock(current.num_lock));
+                    }
+                    locks = current;
+                }
+            },
+        )
+    })
+}
+
+fn led_on(brightness: &Path) -> bool {
+    std::fs::read_to_string(brightness).is_ok_and(|brightness| brightness.trim() != "0")
+}
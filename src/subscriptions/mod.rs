@@ -1,3 +1,7 @@
 pub mod dbus;
+pub mod language;
+pub mod lock_keys;
+pub mod mpris;
 pub mod polkit_agent;
 pub mod polkit_agent_helper;
+pub mod upower;
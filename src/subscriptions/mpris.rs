@@ -0,0 +1,413 @@
+// Watches media players on the session bus, so play/pause and track changes can be shown
+
+use cosmic::iced::Subscription;
+use cosmic::iced::futures::{FutureExt, StreamExt, stream};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedValue};
+use zbus::{MatchRule, MessageStream};
+
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_NAMESPACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// How close to its end a track counts as played through, allowing for the
+/// estimated position drifting from the player's
+const TRACK_END_MARGIN: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Play,
+    Pause,
+    Stop,
+    SkipForward,
+    SkipBackward,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Media {
+    pub action: Action,
+    pub title: String,
+    pub artist: String,
+    /// Album art, when the player provides it as a local file
+    pub art: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Player {
+    status: String,
+    track_id: String,
+    title: String,
+    artist: String,
+    art_url: String,
+    /// Position of the track on its album
+    track_number: Option<i32>,
+    length: Option<Duration>,
+    /// Playback position as of `playing_since`, or now when not playing. Players
+    /// don't signal position changes, so this is kept from seeks and the clock.
+    position: Duration,
+    playing_since: Option<Instant>,
+}
+
+impl Player {
+    fn same_track(&self, other: &Self) -> bool {
+        self.track_id == other.track_id && self.title == other.title
+    }
+
+    fn update_metadata(&mut self, metadata: &HashMap<String, OwnedValue>) {
+        let string = |key| {
+            metadata
+                .get(key)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        self.track_id = metadata
+            .get("mpris:trackid")
+            .and_then(|value| value.downcast_ref::<ObjectPath>().ok())
+            .map(|path| path.to_string())
+            .unwrap_or_else(|| string("mpris:trackid"));
+        self.title = string("xesam:title");
+        self.artist = metadata
+            .get("xesam:artist")
+            .and_then(|value| value.try_clone().ok())
+            .and_then(|value| Vec::<String>::try_from(value).ok())
+            .map(|artists| artists.join(", "))
+            .unwrap_or_default();
+        self.art_url = string("mpris:artUrl");
+        self.track_number = metadata
+            .get("xesam:trackNumber")
+            .and_then(|value| value.downcast_ref::<i32>().ok());
+        // Microseconds, which some players send unsigned
+        self.length = metadata
+            .get("mpris:length")
+            .and_then(|value| {
+                value
+                    .downcast_ref::<i64>()
+                    .ok()
+                    .or_else(|| value.downcast_ref::<u64>().ok()?.try_into().ok())
+            })
+            .and_then(micros);
+    }
+
+    fn position(&self) -> Duration {
+        self.position
+            + self
+                .playing_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// Move the position to `position`, counting on from now if playing
+    fn seek(&mut self, position: Duration) {
+        self.position = position;
+        self.playing_since = (self.status == "Playing").then(Instant::now);
+    }
+
+    /// Keep the position clock in step with a change from `previous`
+    fn follow(&mut self, previous: &Self) {
+        if !previous.same_track(self) {
+            self.seek(Duration::ZERO);
+        } else if previous.status != self.status {
+            self.seek(previous.position());
+        }
+    }
+
+    fn near_end(&self) -> bool {
+        self.length
+            .is_some_and(|length| self.position() + TRACK_END_MARGIN >= length)
+    }
+
+    /// Whether the track has played through, so the player moved on by itself.
+    /// Without a length, that can't be told apart from skipping.
+    fn finished(&self) -> bool {
+        self.length.is_none() || self.near_end()
+    }
+
+    /// Whether this track comes before `previous`, as far as the player says
+    fn is_before(&self, previous: &Self) -> bool {
+        self.track_number
+            .zip(previous.track_number)
+            .is_some_and(|(number, previous)| number < previous)
+    }
+
+    /// Path of the album art, from its percent-encoded `file://` URL
+    fn art(&self) -> Option<PathBuf> {
+        url::Url::parse(&self.art_url)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer {
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    /// Microseconds into the track
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+}
+
+fn micros(micros: i64) -> Option<Duration> {
+    u64::try_from(micros).ok().map(Duration::from_micros)
+}
+
+pub fn subscription(connection: zbus::Connection) -> Subscription<Media> {
+    struct Wrapper {
+        id: &'static str,
+        conn: zbus::Connection,
+    }
+
+    impl Hash for Wrapper {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    Subscription::run_with(
+        Wrapper {
+            id: "mpris",
+            conn: connection,
+        },
+        |Wrapper { id: _id, conn }| {
+            let connection = conn.clone();
+            async move {
+                match watch(connection).await {
+                    Ok(stream) => stream.boxed(),
+                    Err(err) => {
+                        log::error!("Failed to watch media players: {}", err);
+                        stream::pending().boxed()
+                    }
+                }
+            }
+            .flatten_stream()
+        },
+    )
+}
+
+async fn watch(
+    connection: zbus::Connection,
+) -> zbus::Result<impl stream::Stream<Item = Media> + Send> {
+    // Every player exports the same path, so one rule covers all of them
+    let changes = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path(MPRIS_PATH)?
+        .arg(0, PLAYER_INTERFACE)?
+        .build();
+    // Jumps in position, which aren't signalled as property changes
+    let seeks = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface(PLAYER_INTERFACE)?
+        .member("Seeked")?
+        .path(MPRIS_PATH)?
+        .build();
+    // Players coming and going
+    let owners = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameOwnerChanged")?
+        .arg0ns(MPRIS_NAMESPACE)?
+        .build();
+    let messages = stream::select_all([
+        MessageStream::for_match_rule(changes, &connection, None).await?,
+        MessageStream::for_match_rule(seeks, &connection, None).await?,
+        MessageStream::for_match_rule(owners, &connection, None).await?,
+    ]);
+
+    // Players already running, so their first change is compared with what they
+    // were doing
+    let mut players = HashMap::new();
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    for name in dbus.list_names().await? {
+        if !name
+            .as_str()
+            .strip_prefix(MPRIS_NAMESPACE)
+            .is_some_and(|rest| rest.starts_with('.'))
+        {
+            continue;
+        }
+        let owner = match dbus.get_name_owner((*name).clone()).await {
+            Ok(owner) => owner.to_string(),
+            Err(err) => {
+                log::debug!("Failed to find the owner of {}: {}", name, err);
+                continue;
+            }
+        };
+        add_player(&connection, &mut players, owner).await;
+    }
+
+    Ok(stream::unfold(
+        (messages, players, connection),
+        |(mut messages, mut players, connection)| async move {
+            loop {
+                let message = match messages.next().await? {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::debug!("Failed to receive media player signal: {}", err);
+                        continue;
+                    }
+                };
+                let media = match message.header().member().map(|member| member.as_str()) {
+                    Some("NameOwnerChanged") => {
+                        owner_changed(&connection, &mut players, &message).await;
+                        None
+                    }
+                    Some("Seeked") => {
+                        seeked(&mut players, &message);
+                        None
+                    }
+                    _ => player_changed(&connection, &mut players, &message).await,
+                };
+                if let Some(media) = media {
+                    return Some((media, (messages, players, connection)));
+                }
+            }
+        },
+    ))
+}
+
+/// Read the state of the player owning the unique name `owner`
+async fn read_player(connection: &zbus::Connection, owner: &str) -> zbus::Result<Player> {
+    let proxy = MediaPlayerProxy::builder(connection)
+        .destination(owner.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let mut player = Player {
+        status: proxy.playback_status().await?,
+        ..Player::default()
+    };
+    player.update_metadata(&proxy.metadata().await?);
+    // Not every player knows its position
+    player.seek(
+        proxy
+            .position()
+            .await
+            .ok()
+            .and_then(micros)
+            .unwrap_or_default(),
+    );
+    Ok(player)
+}
+
+async fn add_player(
+    connection: &zbus::Connection,
+    players: &mut HashMap<String, Player>,
+    owner: String,
+) {
+    match read_player(connection, &owner).await {
+        Ok(player) => {
+            players.insert(owner, player);
+        }
+        Err(err) => log::debug!("Failed to read media player {}: {}", owner, err),
+    }
+}
+
+/// Track players as they start, and forget them once they quit
+async fn owner_changed(
+    connection: &zbus::Connection,
+    players: &mut HashMap<String, Player>,
+    message: &zbus::Message,
+) {
+    let Ok((_, old_owner, new_owner)) = message.body().deserialize::<(String, String, String)>()
+    else {
+        return;
+    };
+    if !old_owner.is_empty() {
+        players.remove(&old_owner);
+    }
+    if !new_owner.is_empty() {
+        add_player(connection, players, new_owner).await;
+    }
+}
+
+fn seeked(players: &mut HashMap<String, Player>, message: &zbus::Message) {
+    let header = message.header();
+    let (Some(sender), Ok(position)) = (header.sender(), message.body().deserialize::<i64>())
+    else {
+        return;
+    };
+    let position = micros(position).unwrap_or_default();
+    if let Some(player) = players.get_mut(sender.as_str())
+        // Some players jump back to the start as a track ends, ahead of changing
+        // to the next one, which would make that look like a skip
+        && !(position.is_zero() && player.near_end())
+    {
+        player.seek(position);
+    }
+}
+
+async fn player_changed(
+    connection: &zbus::Connection,
+    players: &mut HashMap<String, Player>,
+    message: &zbus::Message,
+) -> Option<Media> {
+    let header = message.header();
+    let sender = header.sender()?.to_string();
+    let (_, changed, _): (String, HashMap<String, OwnedValue>, Vec<String>) =
+        message.body().deserialize().ok()?;
+    if !changed.contains_key("PlaybackStatus") && !changed.contains_key("Metadata") {
+        return None;
+    }
+
+    let previous = players.get(&sender).cloned();
+    let mut player = match &previous {
+        Some(previous) => previous.clone(),
+        // Players are read as they start, so this one was missed. Properties missing
+        // from the signal are read now.
+        None => read_player(connection, &sender).await.unwrap_or_default(),
+    };
+
+    if let Some(status) = changed.get("PlaybackStatus") {
+        player.status = status.downcast_ref::<&str>().ok()?.to_string();
+    }
+    if let Some(metadata) = changed.get("Metadata") {
+        let metadata = HashMap::<String, OwnedValue>::try_from(metadata.try_clone().ok()?).ok()?;
+        player.update_metadata(&metadata);
+    }
+    if let Some(previous) = &previous {
+        player.follow(previous);
+    }
+    players.insert(sender, player.clone());
+
+    let action = match &previous {
+        Some(previous) if previous.status == player.status => {
+            // Moving on at the end of a track isn't the user's doing
+            if previous.same_track(&player) || player.title.is_empty() || previous.finished() {
+                return None;
+            }
+            if player.is_before(previous) {
+                Action::SkipBackward
+            } else {
+                Action::SkipForward
+            }
+        }
+        _ => match player.status.as_str() {
+            "Playing" => Action::Play,
+            "Paused" => Action::Pause,
+            // A stopped player appearing isn't worth an indicator
+            "Stopped" if previous.is_some() => Action::Stop,
+            _ => return None,
+        },
+    };
+
+    Some(Media {
+        action,
+        art: player.art(),
+        title: player.title,
+        artist: player.artist,
+    })
+}
@@ -2,16 +2,23 @@
 
 use cosmic::iced::Subscription;
 use cosmic::iced::futures::FutureExt;
+use i18n_embed::DesktopLanguageRequester;
+use i18n_embed::unic_langid::LanguageIdentifier;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::{Arc, Mutex};
-use tokio::sync::{mpsc, oneshot};
+use std::time::Duration;
+use tokio::sync::{OnceCell, mpsc, oneshot};
 use tokio_stream::wrappers::ReceiverStream;
 use zbus::zvariant;
 
+use crate::accounts;
 use crate::components::polkit_dialog;
+use crate::logind_session::LogindSessionProxy;
 
 const OBJECT_PATH: &str = "/com/system76/CosmicOsd";
+/// Longest wait between attempts to register again
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 pub fn subscription(system_connection: zbus::Connection) -> Subscription<Event> {
     struct Wrapper {
@@ -52,7 +59,12 @@ pub fn subscription(system_connection: zbus::Connection) -> Subscription<Event>
 #[derive(Clone, Debug)]
 pub enum Event {
     CreateDialog(polkit_dialog::Params),
-    CancelDialog { cookie: String },
+    CancelDialog {
+        cookie: String,
+    },
+    /// Send the languages the UI switched to, once their translations are loaded,
+    /// so polkit's messages follow
+    Languages(mpsc::UnboundedSender<Vec<LanguageIdentifier>>),
 }
 
 #[allow(dead_code)]
@@ -88,16 +100,6 @@ impl<'a> zvariant::Type for Identity<'a> {
         <(String, HashMap<String, zvariant::Value>)>::SIGNATURE;
 }
 
-#[zbus::proxy(
-    default_service = "org.freedesktop.login1",
-    interface = "org.freedesktop.login1.Session",
-    default_path = "/org/freedesktop/login1/session/auto"
-)]
-trait LogindSession {
-    #[zbus(property)]
-    fn id(&self) -> zbus::Result<String>;
-}
-
 #[zbus::proxy(
     default_service = "org.freedesktop.PolicyKit1",
     interface = "org.freedesktop.PolicyKit1.Authority",
@@ -115,10 +117,34 @@ trait PolkitAuthority {
         subject: Subject<'_>,
         object_path: &str,
     ) -> zbus::Result<()>;
+    /// action ID, description, message, vendor name, vendor URL, icon name, implicit
+    /// authorizations for any, inactive and active sessions, annotations
+    #[allow(clippy::type_complexity)]
+    fn enumerate_actions(
+        &self,
+        locale: &str,
+    ) -> zbus::Result<
+        Vec<(
+            String,
+            String,
+            String,
+            String,
+            String,
+            String,
+            u32,
+            u32,
+            u32,
+            HashMap<String, String>,
+        )>,
+    >;
 }
 
 struct PolkitAgent {
     sender: mpsc::Sender<Event>,
+    /// Locale the agent is registered with
+    locale: String,
+    /// Vendor of each action, listed on the first authentication after registering
+    vendors: OnceCell<HashMap<String, String>>,
 }
 
 #[zbus::interface(name = "org.freedesktop.PolicyKit1.AuthenticationAgent")]
@@ -131,22 +157,42 @@ impl PolkitAgent {
         details: HashMap<String, String>,
         cookie: String,
         identities: Vec<Identity<'_>>,
+        #[zbus(connection)] connection: &zbus::Connection,
     ) -> Result<(), PolkitError> {
-        if let Some((_uid, pw_name)) = select_user_from_identities(&identities) {
+        let uids = unix_users(&identities, group_members);
+        let users = accounts::users(connection, &uids).await;
+        let uids = users.iter().map(|user| user.uid).collect::<Vec<_>>();
+        if let Some(selected) = select_user(&uids, uzers::get_current_uid()) {
+            let pw_name = users[selected].name.clone();
             let (response_sender, response_receiver) = oneshot::channel();
             let icon_name = if !icon_name.is_empty() {
                 Some(icon_name)
             } else {
                 None
             };
+            let vendor = match self
+                .vendors
+                .get_or_try_init(|| vendors(connection, &self.locale))
+                .await
+            {
+                Ok(vendors) => vendors.get(&action_id).cloned(),
+                Err(err) => {
+                    log::debug!("Failed to look up polkit action {}: {}", action_id, err);
+                    None
+                }
+            };
+            let program = requesting_program(&details);
             let _ = self
                 .sender
                 .send(Event::CreateDialog(polkit_dialog::Params {
                     pw_name,
+                    users,
                     action_id,
                     message,
                     icon_name,
                     details,
+                    vendor,
+                    program,
                     cookie,
                     response_sender: Arc::new(Mutex::new(Some(response_sender))),
                 }))
@@ -163,33 +209,93 @@ impl PolkitAgent {
     }
 }
 
-fn select_user_from_identities(identities: &[Identity]) -> Option<(u32, String)> {
+/// Vendor of each action that names one in its policy file. polkit can only list
+/// every action at once, so this is kept until the agent registers again.
+async fn vendors(
+    connection: &zbus::Connection,
+    locale: &str,
+) -> zbus::Result<HashMap<String, String>> {
+    let authority = PolkitAuthorityProxy::new(connection).await?;
+    Ok(authority
+        .enumerate_actions(locale)
+        .await?
+        .into_iter()
+        .filter(|action| !action.3.is_empty())
+        .map(|action| (action.0, action.3))
+        .collect())
+}
+
+/// Executable of the process asking for authorization, from the PID polkit adds to
+/// the details. Other users' executables can't be resolved, so this falls back to
+/// the command line.
+fn requesting_program(details: &HashMap<String, String>) -> Option<String> {
+    let pid: u32 = details.get("polkit.subject-pid")?.parse().ok()?;
+    if let Ok(exe) = std::fs::read_link(format!("/proc/{pid}/exe")) {
+        return Some(exe.display().to_string());
+    }
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    let program = cmdline.split(|b| *b == 0).next()?;
+    (!program.is_empty()).then(|| String::from_utf8_lossy(program).into_owned())
+}
+
+/// UIDs of the users that can authenticate, in the order polkit gave them. Groups,
+/// like `unix-group:wheel` in a rule, are expanded with `members_of`.
+fn unix_users(identities: &[Identity], members_of: impl Fn(u32) -> Vec<u32>) -> Vec<u32> {
     let mut uids = Vec::new();
     for ident in identities {
-        if ident.identity_kind == "unix-user"
+        let members = if ident.identity_kind == "unix-user"
             && let Some(zvariant::Value::U32(uid)) = ident.identity_details.get("uid")
         {
-            uids.push(*uid);
+            vec![*uid]
+        } else if ident.identity_kind == "unix-group"
+            && let Some(zvariant::Value::U32(gid)) = ident.identity_details.get("gid")
+        {
+            members_of(*gid)
+        } else {
+            continue;
+        };
+        for uid in members {
+            if !uids.contains(&uid) {
+                uids.push(uid);
+            }
         }
-        // `unix-group` is apparently a thing too, but Gnome Shell doesn't seem to handle it...
     }
+    uids
+}
 
-    // Like Gnome Shell, try own uid, then root, then first UID in `identities`
-    let uid = *uids
+/// Members of a group from the group database. Users who only have it as their
+/// primary group aren't listed there, so they're left out.
+fn group_members(gid: u32) -> Vec<u32> {
+    let Some(group) = uzers::get_group_by_gid(gid) else {
+        return Vec::new();
+    };
+    group
+        .members()
         .iter()
-        .find(|uid| **uid == uzers::get_current_uid())
-        .or(uids.iter().find(|uid| **uid == 0))
-        .or_else(|| uids.first())?;
+        .filter_map(uzers::get_user_by_name)
+        .map(|user| user.uid())
+        .collect()
+}
 
-    let user = uzers::get_user_by_uid(uid)?;
-    Some((uid, user.name().to_str()?.to_string()))
+/// Index of the user selected at first. Like Gnome Shell, try own uid, then root,
+/// then the first one.
+fn select_user(uids: &[u32], current_uid: u32) -> Option<usize> {
+    uids.iter()
+        .position(|uid| *uid == current_uid)
+        .or_else(|| uids.iter().position(|uid| *uid == 0))
+        .or((!uids.is_empty()).then_some(0))
 }
 
 async fn register_agent(
     system_connection: &zbus::Connection,
     sender: mpsc::Sender<Event>,
 ) -> zbus::Result<()> {
-    let agent = PolkitAgent { sender };
+    let locale = posix_locale(&DesktopLanguageRequester::requested_languages());
+    let agent = PolkitAgent {
+        sender: sender.clone(),
+        locale: locale.clone(),
+        vendors: OnceCell::new(),
+    };
     system_connection
         .object_server()
         .at(OBJECT_PATH, agent)
@@ -197,18 +303,116 @@ async fn register_agent(
 
     let session = LogindSessionProxy::new(system_connection).await?;
     let session_id = session.id().await?;
-
-    let mut subject_details = HashMap::new();
-    subject_details.insert("session-id", session_id.into());
-    let subject = Subject {
+    let subject = || Subject {
         subject_kind: "unix-session",
-        subject_details,
+        subject_details: HashMap::from([("session-id", session_id.as_str().into())]),
     };
 
-    // XXX locale
     let authority = PolkitAuthorityProxy::new(system_connection).await?;
     authority
-        .register_authentication_agent(subject, "en_US", OBJECT_PATH)
+        .register_authentication_agent(subject(), &locale, OBJECT_PATH)
         .await?;
+
+    // polkit translates messages for the locale given when registering, so follow
+    // the language picked in Settings. Failures from here on are logged rather
+    // than returned, so the agent keeps running.
+    let (languages_sender, mut languages) = mpsc::unbounded_channel();
+    if sender
+        .send(Event::Languages(languages_sender))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+    while let Some(requested) = languages.recv().await {
+        let locale = posix_locale(&requested);
+        if let Err(err) = authority
+            .unregister_authentication_agent(subject(), OBJECT_PATH)
+            .await
+        {
+            log::warn!("Failed to unregister PolicyKit agent: {}", err);
+        }
+        let mut delay = Duration::from_secs(1);
+        while let Err(err) = authority
+            .register_authentication_agent(subject(), &locale, OBJECT_PATH)
+            .await
+        {
+            log::warn!(
+                "Failed to register PolicyKit agent for {}, retrying in {:?}: {}",
+                locale,
+                delay,
+                err
+            );
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+        match system_connection
+            .object_server()
+            .interface::<_, PolkitAgent>(OBJECT_PATH)
+            .await
+        {
+            Ok(agent) => {
+                let mut agent = agent.get_mut().await;
+                agent.locale = locale;
+                // Vendors are translated too
+                agent.vendors = OnceCell::new();
+            }
+            Err(err) => log::warn!("Failed to update PolicyKit agent locale: {}", err),
+        }
+    }
     Ok(())
 }
+
+/// POSIX locale of the language the UI was negotiated into, like `de_DE.UTF-8`. The
+/// translations are mostly per language, so the region comes from the most
+/// preferred of `requested` that has it.
+fn posix_locale(requested: &[LanguageIdentifier]) -> String {
+    let current = crate::LANG_LOADER.current_language();
+    let language = requested
+        .iter()
+        .cloned()
+        .find(|requested| requested.language == current.language && requested.region.is_some())
+        .unwrap_or(current);
+    match language.region {
+        Some(region) => format!("{}_{}.UTF-8", language.language, region),
+        None => format!("{}.UTF-8", language.language),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(kind: &'static str, key: &'static str, id: u32) -> Identity<'static> {
+        Identity {
+            identity_kind: kind,
+            identity_details: HashMap::from([(key, zvariant::Value::U32(id))]),
+        }
+    }
+
+    fn wheel(gid: u32) -> Vec<u32> {
+        match gid {
+            10 => vec![1001, 0, 1002],
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn expands_groups_to_members() {
+        let identities = [
+            identity("unix-user", "uid", 1002),
+            identity("unix-group", "gid", 10),
+            identity("unix-group", "gid", 27),
+            identity("unix-netgroup", "name", 0),
+        ];
+        assert_eq!(unix_users(&identities, wheel), [1002, 1001, 0]);
+    }
+
+    #[test]
+    fn selects_own_user_then_root_then_first() {
+        assert_eq!(select_user(&[1001, 0, 1000], 1000), Some(2));
+        assert_eq!(select_user(&[1001, 0, 1002], 1000), Some(1));
+        assert_eq!(select_user(&[1001, 1002], 1000), Some(0));
+        assert_eq!(select_user(&[], 1000), None);
+    }
+}
@@ -0,0 +1,138 @@
+// Charger connect/disconnect events, with the battery state from the UPower display device
+
+use cosmic::iced::Subscription;
+use cosmic::iced::futures::{FutureExt, StreamExt, stream};
+use std::hash::Hash;
+use std::time::Duration;
+use zbus::proxy::CacheProperties;
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower.Device",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+trait DisplayDevice {
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn icon_name(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Power {
+    pub plugged_in: bool,
+    /// Battery charge, from 0 to 100
+    pub percentage: f64,
+    pub icon_name: String,
+    /// Time until the battery is full when plugged in, or empty when not
+    pub time: Option<Duration>,
+}
+
+pub fn subscription(system_connection: zbus::Connection) -> Subscription<Power> {
+    struct Wrapper {
+        id: &'static str,
+        conn: zbus::Connection,
+    }
+
+    impl Hash for Wrapper {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    Subscription::run_with(
+        Wrapper {
+            id: "upower-charger",
+            conn: system_connection,
+        },
+        |Wrapper { id: _id, conn }| {
+            let connection = conn.clone();
+            async move {
+                match watch(connection).await {
+                    Ok(stream) => stream.boxed(),
+                    Err(err) => {
+                        log::error!("Failed to watch UPower: {}", err);
+                        stream::pending().boxed()
+                    }
+                }
+            }
+            .flatten_stream()
+        },
+    )
+}
+
+async fn watch(
+    connection: zbus::Connection,
+) -> zbus::Result<impl stream::Stream<Item = Power> + Send> {
+    let upower = UPowerProxy::new(&connection).await?;
+    let device = DisplayDeviceProxy::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // Without a battery there is nothing worth showing when the charger changes
+    if !device.is_present().await.unwrap_or(false) {
+        return Ok(stream::pending().boxed());
+    }
+
+    let on_battery = upower.on_battery().await?;
+    let changes = upower.receive_on_battery_changed().await;
+
+    Ok(stream::unfold(
+        (changes, on_battery, device),
+        |(mut changes, mut on_battery, device)| async move {
+            loop {
+                let changed = changes.next().await?.get().await.ok()?;
+                // The first item repeats the current value
+                if changed == on_battery {
+                    continue;
+                }
+                on_battery = changed;
+
+                let power = power(&device, !on_battery).await;
+                return Some((power, (changes, on_battery, device)));
+            }
+        },
+    )
+    .boxed())
+}
+
+async fn power(device: &DisplayDeviceProxy<'_>, plugged_in: bool) -> Power {
+    let seconds = if plugged_in {
+        device.time_to_full().await
+    } else {
+        device.time_to_empty().await
+    };
+
+    Power {
+        plugged_in,
+        percentage: device.percentage().await.unwrap_or_default(),
+        icon_name: device
+            .icon_name()
+            .await
+            .ok()
+            .filter(|icon| !icon.is_empty())
+            .unwrap_or_else(|| "battery-symbolic".to_string()),
+        // UPower reports 0 until it has an estimate
+        time: seconds
+            .ok()
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| Duration::from_secs(seconds as u64)),
+    }
+}
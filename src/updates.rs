@@ -0,0 +1,113 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Updates waiting on a restart: an offline update prepared by PackageKit, or
+//! firmware staged by fwupd.
+
+use std::collections::HashMap;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, proxy};
+
+// `FwupdUpdateState` values for an update finished by the next boot
+const FWUPD_UPDATE_STATE_PENDING: u32 = 1;
+const FWUPD_UPDATE_STATE_NEEDS_REBOOT: u32 = 4;
+
+#[proxy(
+    interface = "org.freedesktop.PackageKit.Offline",
+    default_service = "org.freedesktop.PackageKit",
+    default_path = "/org/freedesktop/PackageKit"
+)]
+trait PackageKitOffline {
+    /// Install the prepared update on the next boot, then `"reboot"` or `"power-off"`
+    fn trigger(&self, action: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn update_prepared(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.fwupd",
+    default_service = "org.freedesktop.fwupd",
+    default_path = "/"
+)]
+trait Fwupd {
+    fn get_devices(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Updates {
+    /// PackageKit has downloaded an update to install while booting
+    pub offline: bool,
+    /// fwupd has firmware that the next boot installs, whatever is chosen
+    pub firmware: bool,
+}
+
+impl Updates {
+    pub fn any(self) -> bool {
+        self.offline || self.firmware
+    }
+}
+
+/// Updates waiting on a restart. Either service may be missing, so failures are
+/// logged and treated as nothing pending.
+pub async fn pending() -> Updates {
+    let connection = match Connection::system().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            log::debug!("Failed to check for pending updates: {}", err);
+            return Updates::default();
+        }
+    };
+
+    let offline = match offline_prepared(&connection).await {
+        Ok(prepared) => prepared,
+        Err(err) => {
+            log::debug!("Failed to check for a prepared offline update: {}", err);
+            false
+        }
+    };
+    let firmware = match firmware_staged(&connection).await {
+        Ok(staged) => staged,
+        Err(err) => {
+            log::debug!("Failed to check for staged firmware updates: {}", err);
+            false
+        }
+    };
+    Updates { offline, firmware }
+}
+
+async fn offline_prepared(connection: &Connection) -> zbus::Result<bool> {
+    PackageKitOfflineProxy::new(connection)
+        .await?
+        .update_prepared()
+        .await
+}
+
+async fn firmware_staged(connection: &Connection) -> zbus::Result<bool> {
+    let devices = FwupdProxy::new(connection).await?.get_devices().await?;
+    Ok(devices.iter().any(|device| {
+        device
+            .get("UpdateState")
+            .and_then(|state| u32::try_from(state).ok())
+            .is_some_and(|state| {
+                matches!(
+                    state,
+                    FWUPD_UPDATE_STATE_PENDING | FWUPD_UPDATE_STATE_NEEDS_REBOOT
+                )
+            })
+    }))
+}
+
+/// Have PackageKit install its prepared update on the next boot, then power off
+/// or restart again. Returns whether there was one to install.
+pub async fn install_offline(power_off: bool) -> zbus::Result<bool> {
+    let connection = Connection::system().await?;
+    let packagekit = PackageKitOfflineProxy::new(&connection).await?;
+    if !packagekit.update_prepared().await? {
+        return Ok(false);
+    }
+    packagekit
+        .trigger(if power_off { "power-off" } else { "reboot" })
+        .await?;
+    Ok(true)
+}